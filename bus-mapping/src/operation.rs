@@ -91,7 +91,7 @@ impl RWCounter {
 }
 
 /// Enum used to differenciate between EVM Stack, Memory and Storage operations.
-#[derive(Debug, Clone, PartialEq, Eq, Copy, EnumIter, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy, EnumIter, Hash)]
 pub enum Target {
     /// Start is a padding operation.
     Start = 1,
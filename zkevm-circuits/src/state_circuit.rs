@@ -238,7 +238,7 @@ impl<F: Field> StateCircuitConfig<F> {
 
             self.sort_keys
                 .rw_counter
-                .assign(region, offset, row.rw_counter() as u32)?;
+                .assign(region, offset, row.rw_counter().0 as u32)?;
 
             if let Some(id) = row.id() {
                 self.sort_keys.id.assign(region, offset, id as u32)?;
@@ -294,7 +294,7 @@ fn rw_to_be_limbs(row: &Rw) -> Vec<u16> {
     be_bytes.push(0u8);
     be_bytes.push(row.field_tag().unwrap_or_default() as u8);
     be_bytes.extend_from_slice(&(row.storage_key().unwrap_or_default().to_be_bytes()));
-    be_bytes.extend_from_slice(&((row.rw_counter() as u32).to_be_bytes()));
+    be_bytes.extend_from_slice(&((row.rw_counter().0 as u32).to_be_bytes()));
 
     be_bytes
         .iter()
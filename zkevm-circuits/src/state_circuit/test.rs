@@ -2,7 +2,7 @@ pub use super::{dev::*, *};
 use crate::{
     table::{AccountFieldTag, CallContextFieldTag, TxLogFieldTag, TxReceiptFieldTag},
     util::{unusable_rows, SubCircuit},
-    witness::{MptUpdates, Rw, RwMap},
+    witness::{MptUpdates, Rw, RwCounter, RwMap},
 };
 use bus_mapping::operation::{
     MemoryOp, Operation, OperationContainer, RWCounter, StackOp, StorageOp, RW,
@@ -237,14 +237,14 @@ fn lexicographic_ordering_test_2() {
 fn first_access_for_stack_is_write() {
     let rows = vec![
         Rw::Stack {
-            rw_counter: 24,
+            rw_counter: RwCounter(24),
             is_write: true,
             call_id: 1,
             stack_pointer: 1022,
             value: U256::from(394500u64),
         },
         Rw::Stack {
-            rw_counter: 25,
+            rw_counter: RwCounter(25),
             is_write: false,
             call_id: 1,
             stack_pointer: 1022,
@@ -259,7 +259,7 @@ fn first_access_for_stack_is_write() {
 fn diff_1_problem_repro() {
     let rows = vec![
         Rw::Account {
-            rw_counter: 1,
+            rw_counter: RwCounter(1),
             is_write: true,
             account_address: Address::default(),
             field_tag: AccountFieldTag::CodeHash,
@@ -267,7 +267,7 @@ fn diff_1_problem_repro() {
             value_prev: U256::zero(),
         },
         Rw::Account {
-            rw_counter: 2,
+            rw_counter: RwCounter(2),
             is_write: true,
             account_address: Address::default(),
             field_tag: AccountFieldTag::CodeHash,
@@ -283,14 +283,14 @@ fn diff_1_problem_repro() {
 fn tx_log_ok() {
     let rows = vec![
         Rw::Stack {
-            rw_counter: 1,
+            rw_counter: RwCounter(1),
             is_write: true,
             call_id: 1,
             stack_pointer: 1023,
             value: U256::from(394500u64),
         },
         Rw::TxLog {
-            rw_counter: 2,
+            rw_counter: RwCounter(2),
             is_write: true,
             tx_id: 1,
             log_id: 1,
@@ -299,7 +299,7 @@ fn tx_log_ok() {
             value: U256::one(),
         },
         Rw::TxLog {
-            rw_counter: 3,
+            rw_counter: RwCounter(3),
             is_write: true,
             tx_id: 1,
             log_id: 1,
@@ -308,7 +308,7 @@ fn tx_log_ok() {
             value: U256::one(),
         },
         Rw::TxLog {
-            rw_counter: 4,
+            rw_counter: RwCounter(4),
             is_write: true,
             tx_id: 1,
             log_id: 1,
@@ -317,7 +317,7 @@ fn tx_log_ok() {
             value: U256::from(2u64),
         },
         Rw::TxLog {
-            rw_counter: 5,
+            rw_counter: RwCounter(5),
             is_write: true,
             tx_id: 1,
             log_id: 1,
@@ -326,7 +326,7 @@ fn tx_log_ok() {
             value: U256::from(3u64),
         },
         Rw::TxLog {
-            rw_counter: 6,
+            rw_counter: RwCounter(6),
             is_write: true,
             tx_id: 1,
             log_id: 1,
@@ -343,7 +343,7 @@ fn tx_log_ok() {
 fn tx_log_bad() {
     // is_write is false
     let rows = vec![Rw::TxLog {
-        rw_counter: 2,
+        rw_counter: RwCounter(2),
         is_write: false,
         tx_id: 1,
         log_id: 1,
@@ -358,7 +358,7 @@ fn tx_log_bad() {
 #[test]
 fn address_limb_mismatch() {
     let rows = vec![Rw::Account {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: address!("0x000000000000000000000000000000000cafe002"),
         field_tag: AccountFieldTag::CodeHash,
@@ -375,7 +375,7 @@ fn address_limb_mismatch() {
 #[test]
 fn address_limb_out_of_range() {
     let rows = vec![Rw::Account {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: address!("0x0000000000000000000000000000000000010000"),
         field_tag: AccountFieldTag::CodeHash,
@@ -395,7 +395,7 @@ fn address_limb_out_of_range() {
 #[test]
 fn storage_key_mismatch() {
     let rows = vec![Rw::AccountStorage {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: Address::default(),
         storage_key: U256::from(6),
@@ -414,7 +414,7 @@ fn storage_key_mismatch() {
 #[test]
 fn is_write_nonbinary() {
     let rows = vec![Rw::CallContext {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         call_id: 0,
         field_tag: CallContextFieldTag::TxId,
@@ -430,14 +430,14 @@ fn is_write_nonbinary() {
 #[test]
 fn nonlexicographic_order_tag() {
     let first = Rw::Memory {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         call_id: 1,
         memory_address: 10,
         byte: 12,
     };
     let second = Rw::CallContext {
-        rw_counter: 2,
+        rw_counter: RwCounter(2),
         is_write: false,
         call_id: 1,
         field_tag: CallContextFieldTag::IsSuccess,
@@ -451,14 +451,14 @@ fn nonlexicographic_order_tag() {
 #[test]
 fn nonlexicographic_order_field_tag() {
     let first = Rw::CallContext {
-        rw_counter: 5,
+        rw_counter: RwCounter(5),
         is_write: true,
         call_id: 0,
         field_tag: CallContextFieldTag::RwCounterEndOfReversion,
         value: U256::from(100),
     };
     let second = Rw::CallContext {
-        rw_counter: 2,
+        rw_counter: RwCounter(2),
         is_write: true,
         call_id: 0,
         field_tag: CallContextFieldTag::CallerId,
@@ -472,14 +472,14 @@ fn nonlexicographic_order_field_tag() {
 #[test]
 fn nonlexicographic_order_id() {
     let first = Rw::CallContext {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         call_id: 0,
         field_tag: CallContextFieldTag::RwCounterEndOfReversion,
         value: U256::from(100),
     };
     let second = Rw::CallContext {
-        rw_counter: 2,
+        rw_counter: RwCounter(2),
         is_write: true,
         call_id: 1,
         field_tag: CallContextFieldTag::RwCounterEndOfReversion,
@@ -493,7 +493,7 @@ fn nonlexicographic_order_id() {
 #[test]
 fn nonlexicographic_order_address() {
     let first = Rw::Account {
-        rw_counter: 50,
+        rw_counter: RwCounter(50),
         is_write: true,
         account_address: address!("0x1000000000000000000000000000000000000000"),
         field_tag: AccountFieldTag::CodeHash,
@@ -501,7 +501,7 @@ fn nonlexicographic_order_address() {
         value_prev: U256::zero(),
     };
     let second = Rw::Account {
-        rw_counter: 30,
+        rw_counter: RwCounter(30),
         is_write: true,
         account_address: address!("0x2000000000000000000000000000000000000000"),
         field_tag: AccountFieldTag::CodeHash,
@@ -516,7 +516,7 @@ fn nonlexicographic_order_address() {
 #[test]
 fn nonlexicographic_order_storage_key_upper() {
     let first = Rw::AccountStorage {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: Address::default(),
         storage_key: U256::zero(),
@@ -526,7 +526,7 @@ fn nonlexicographic_order_storage_key_upper() {
         committed_value: U256::from(800),
     };
     let second = Rw::AccountStorage {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: Address::default(),
         storage_key: U256::MAX - U256::one(),
@@ -543,7 +543,7 @@ fn nonlexicographic_order_storage_key_upper() {
 #[test]
 fn nonlexicographic_order_storage_key_lower() {
     let first = Rw::AccountStorage {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: Address::default(),
         storage_key: U256::zero(),
@@ -553,7 +553,7 @@ fn nonlexicographic_order_storage_key_lower() {
         committed_value: U256::from(200),
     };
     let second = Rw::AccountStorage {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         account_address: Address::default(),
         storage_key: U256::one(),
@@ -570,14 +570,14 @@ fn nonlexicographic_order_storage_key_lower() {
 #[test]
 fn nonlexicographic_order_rw_counter() {
     let first = Rw::CallContext {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         call_id: 1,
         field_tag: CallContextFieldTag::IsSuccess,
         value: U256::zero(),
     };
     let second = Rw::CallContext {
-        rw_counter: 2,
+        rw_counter: RwCounter(2),
         is_write: false,
         call_id: 1,
         field_tag: CallContextFieldTag::IsSuccess,
@@ -592,14 +592,14 @@ fn nonlexicographic_order_rw_counter() {
 fn lexicographic_ordering_previous_limb_differences_nonzero() {
     let rows = vec![
         Rw::TxRefund {
-            rw_counter: 1,
+            rw_counter: RwCounter(1),
             is_write: true,
             tx_id: 23,
             value: 20,
             value_prev: 0,
         },
         Rw::Account {
-            rw_counter: 2,
+            rw_counter: RwCounter(2),
             is_write: true,
             account_address: address!("0x0000000000000000000000000000000000000001"),
             field_tag: AccountFieldTag::Nonce,
@@ -628,14 +628,14 @@ fn lexicographic_ordering_previous_limb_differences_nonzero() {
 fn read_inconsistency() {
     let rows = vec![
         Rw::Memory {
-            rw_counter: 10,
+            rw_counter: RwCounter(10),
             is_write: false,
             call_id: 1,
             memory_address: 10,
             byte: 0,
         },
         Rw::Memory {
-            rw_counter: 40,
+            rw_counter: RwCounter(40),
             is_write: false,
             call_id: 1,
             memory_address: 10,
@@ -672,7 +672,7 @@ fn skipped_start_rw_counter() {
 #[test]
 fn invalid_memory_address() {
     let rows = vec![Rw::Memory {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         call_id: 1,
         memory_address: 1u64 << 32,
@@ -685,7 +685,7 @@ fn invalid_memory_address() {
 #[test]
 fn bad_initial_memory_value() {
     let rows = vec![Rw::Memory {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         call_id: 1,
         memory_address: 10,
@@ -712,7 +712,7 @@ fn bad_initial_memory_value() {
 #[test]
 fn invalid_memory_value() {
     let rows = vec![Rw::Memory {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         call_id: 1,
         memory_address: 10,
@@ -733,7 +733,7 @@ fn invalid_memory_value() {
 #[test]
 fn stack_read_before_write() {
     let rows = vec![Rw::Stack {
-        rw_counter: 9,
+        rw_counter: RwCounter(9),
         is_write: false,
         call_id: 3,
         stack_pointer: 200,
@@ -746,7 +746,7 @@ fn stack_read_before_write() {
 #[test]
 fn invalid_stack_address() {
     let rows = vec![Rw::Stack {
-        rw_counter: 9,
+        rw_counter: RwCounter(9),
         is_write: true,
         call_id: 3,
         stack_pointer: 3000,
@@ -760,14 +760,14 @@ fn invalid_stack_address() {
 fn invalid_stack_address_change() {
     let rows = vec![
         Rw::Stack {
-            rw_counter: 9,
+            rw_counter: RwCounter(9),
             is_write: true,
             call_id: 3,
             stack_pointer: 100,
             value: U256::from(10),
         },
         Rw::Stack {
-            rw_counter: 13,
+            rw_counter: RwCounter(13),
             is_write: true,
             call_id: 3,
             stack_pointer: 102,
@@ -807,7 +807,7 @@ fn invalid_tags() {
 #[test]
 fn bad_initial_stack_value() {
     let rows = vec![Rw::Stack {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         call_id: 1,
         stack_pointer: 10,
@@ -830,7 +830,7 @@ fn bad_initial_stack_value() {
 #[test]
 fn bad_initial_tx_access_list_account_value() {
     let rows = vec![Rw::TxAccessListAccount {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         tx_id: 1,
         account_address: address!("0x0000000000000000000000000000000004356002"),
@@ -854,7 +854,7 @@ fn bad_initial_tx_access_list_account_value() {
 #[test]
 fn bad_initial_tx_refund_value() {
     let rows = vec![Rw::TxRefund {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         tx_id: 1,
         value: 0,
@@ -882,7 +882,7 @@ fn bad_initial_tx_refund_value() {
 #[test]
 fn bad_initial_tx_log_value() {
     let rows = vec![Rw::TxLog {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: true,
         tx_id: 800,
         log_id: 4,
@@ -908,14 +908,14 @@ fn bad_initial_tx_log_value() {
 fn variadic_size_check() {
     let mut rows = vec![
         Rw::Stack {
-            rw_counter: 24,
+            rw_counter: RwCounter(24),
             is_write: true,
             call_id: 1,
             stack_pointer: 1022,
             value: U256::from(394500u64),
         },
         Rw::Stack {
-            rw_counter: 25,
+            rw_counter: RwCounter(25),
             is_write: false,
             call_id: 1,
             stack_pointer: 1022,
@@ -936,14 +936,14 @@ fn variadic_size_check() {
 
     rows.extend_from_slice(&[
         Rw::Stack {
-            rw_counter: 26,
+            rw_counter: RwCounter(26),
             is_write: true,
             call_id: 1,
             stack_pointer: 1021,
             value: U256::from(394511u64),
         },
         Rw::Stack {
-            rw_counter: 27,
+            rw_counter: RwCounter(27),
             is_write: false,
             call_id: 1,
             stack_pointer: 1021,
@@ -970,7 +970,7 @@ fn variadic_size_check() {
 #[ignore = "TxReceipt constraints not yet implemented"]
 fn bad_initial_tx_receipt_value() {
     let rows = vec![Rw::TxReceipt {
-        rw_counter: 1,
+        rw_counter: RwCounter(1),
         is_write: false,
         tx_id: 3421,
         field_tag: TxReceiptFieldTag::CumulativeGasUsed,
@@ -9,7 +9,10 @@ use crate::{
         word::{self, Word},
         Challenges,
     },
-    witness::{Block, BlockContext, MptUpdateRow, MptUpdates, Rw, RwMap, RwRow, Transaction},
+    witness::{
+        Block, BlockContext, MptUpdateRow, MptUpdates, Rw, RwMap, RwRow, Transaction,
+        RW_ROW_COLUMN_NAMES,
+    },
 };
 use bus_mapping::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep};
 use core::iter::once;
@@ -25,7 +28,7 @@ use halo2_proofs::{
 };
 use itertools::Itertools;
 use std::array;
-use strum_macros::{EnumCount, EnumIter};
+use strum_macros::{EnumCount, EnumIter, IntoStaticStr};
 
 /// block table
 pub(crate) mod block_table;
@@ -120,7 +123,8 @@ impl<F: Field, C: Into<Column<Any>> + Copy, const W: usize> LookupTable<F> for [
 }
 
 /// Tag for an AccountField in RwTable
-#[derive(Clone, Copy, Debug, EnumIter, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, EnumIter, IntoStaticStr, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "rw-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccountFieldTag {
     /// Nonce field
     Nonce = 1,
@@ -134,7 +138,8 @@ pub enum AccountFieldTag {
 impl_expr!(AccountFieldTag);
 
 /// Tag for a CallContextField in RwTable
-#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter, IntoStaticStr)]
+#[cfg_attr(feature = "rw-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CallContextFieldTag {
     /// RwCounterEndOfReversion
     RwCounterEndOfReversion = 1,
@@ -47,22 +47,10 @@ impl<F: Field> LookupTable<F> for RwTable {
     }
 
     fn annotations(&self) -> Vec<String> {
-        vec![
-            String::from("rw_counter"),
-            String::from("is_write"),
-            String::from("tag"),
-            String::from("id"),
-            String::from("address"),
-            String::from("field_tag"),
-            String::from("storage_key_lo"),
-            String::from("storage_key_hi"),
-            String::from("value_lo"),
-            String::from("value_hi"),
-            String::from("value_prev_lo"),
-            String::from("value_prev_hi"),
-            String::from("init_val_lo"),
-            String::from("init_val_hi"),
-        ]
+        RW_ROW_COLUMN_NAMES
+            .iter()
+            .map(|name| String::from(*name))
+            .collect()
     }
 }
 impl RwTable {
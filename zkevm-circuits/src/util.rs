@@ -11,9 +11,10 @@ use halo2_proofs::{
 };
 
 use crate::{table::TxLogFieldTag, witness};
-use eth_types::{keccak256, Field, ToAddress, Word};
+use eth_types::{keccak256, Field, ToAddress, ToWord, Word};
 pub use ethers_core::types::{Address, U256};
 pub use gadgets::util::Expr;
+use strum::IntoEnumIterator;
 
 /// Cell Manager
 pub mod cell_manager;
@@ -128,6 +129,24 @@ pub(crate) fn build_tx_log_address(index: u64, field_tag: TxLogFieldTag, log_id:
         .to_address()
 }
 
+/// Inverse of `build_tx_log_address`, returning the `(index, field_tag,
+/// log_id)` packed in the address of a TxLog row. Panics if the address
+/// doesn't hold a valid `TxLogFieldTag`.
+pub fn decode_tx_log_address(addr: Address) -> (u64, TxLogFieldTag, u64) {
+    try_decode_tx_log_address(addr)
+        .unwrap_or_else(|| panic!("unknown tx log field tag in address {:?}", addr))
+}
+
+/// Same as [`decode_tx_log_address`], returning `None` if the address
+/// doesn't hold a valid `TxLogFieldTag`, e.g. for untrusted input.
+pub(crate) fn try_decode_tx_log_address(addr: Address) -> Option<(u64, TxLogFieldTag, u64)> {
+    let word = addr.to_word();
+    let field_tag =
+        TxLogFieldTag::iter().find(|tag| *tag as u64 == (word.low_u64() >> 32) & 0xffff)?;
+    let log_id: Word = word >> 48;
+    Some((word.low_u64() & 0xffff_ffff, field_tag, log_id.low_u64()))
+}
+
 pub(crate) fn build_tx_log_expression<F: Field>(
     index: Expression<F>,
     field_tag: Expression<F>,
@@ -224,3 +243,24 @@ pub(crate) fn unusable_rows<F: Field, C: Circuit<F>>(params: C::Params) -> usize
 
     cs.blinding_factors() + 1
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn decode_tx_log_address_round_trip() {
+        let mut rng = rand::thread_rng();
+        for field_tag in TxLogFieldTag::iter() {
+            for _ in 0..100 {
+                let index = rng.gen::<u32>() as u64;
+                let log_id = rng.gen::<u64>();
+                assert_eq!(
+                    decode_tx_log_address(build_tx_log_address(index, field_tag, log_id)),
+                    (index, field_tag, log_id)
+                );
+            }
+        }
+    }
+}
@@ -8,4 +8,13 @@ mod mpt;
 pub use mpt::{MptUpdate, MptUpdateRow, MptUpdates};
 mod rw;
 pub use bus_mapping::circuit_input_builder::{Call, ExecStep, Transaction};
-pub use rw::{Rw, RwMap, RwRow};
+#[cfg(any(test, feature = "test-util"))]
+pub use rw::{assert_counters_from_container, assert_deterministic};
+pub use rw::{
+    merge_sorted, unwrap_rows, AccessSets, AccountSummary, CallContextRangeError, CallReturnError,
+    CheckOptions, CodeHashError, ColdAccessError, EncodingError, InvariantError, LifecycleEvent,
+    LogIdError, LookupEstimate, MemoryBoundsError, NonceError, ParseError, PrevChainError,
+    ReceiptStatusError, RefundCapError, Rw, RwBuildError, RwCounter, RwKey, RwMap, RwMapBuilder,
+    RwRow, RwValueError, SortedRwMap, StackInitError, StackPointerError, TooManyRows,
+    TxCounterError, UnwrapError, ValueError, RW_ROW_COLUMN_NAMES,
+};
@@ -64,7 +64,7 @@ impl<F: Field> Block<F> {
                 for rw_idx in 0..step.bus_mapping_instance.len() {
                     let rw = self.get_rws(step, rw_idx);
                     let rw_str = if rw.is_write() { "READ" } else { "WRIT" };
-                    println!("  {} {} {:?}", rw.rw_counter(), rw_str, rw);
+                    println!("  {} {} {:?}", rw.rw_counter().0, rw_str, rw);
                 }
             }
         }
@@ -1,22 +1,34 @@
 //! The Read-Write table related structs
-use std::collections::HashMap;
+#[cfg(test)]
+mod test;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    ops::{Add, Range, RangeInclusive},
+};
 
 use bus_mapping::{
     exec_trace::OperationRef,
     operation::{self, AccountField, CallContextField, Target, TxLogField, TxReceiptField},
+    state_db::CodeDB,
+};
+use eth_types::{
+    evm_types::MAX_REFUND_QUOTIENT_OF_GAS_USED, Address, Field, ToAddress, ToScalar, ToWord, Word,
+    U256,
 };
-use eth_types::{Address, Field, ToAddress, ToScalar, Word, U256};
 use halo2_proofs::circuit::Value;
 use itertools::Itertools;
+use strum::IntoEnumIterator;
 
 use crate::{
     table::{AccountFieldTag, CallContextFieldTag, TxLogFieldTag, TxReceiptFieldTag},
-    util::{build_tx_log_address, word},
+    util::{build_tx_log_address, try_decode_tx_log_address, word},
 };
 
 use super::MptUpdates;
 
-/// Rw constainer for a witness block
+/// Rw constainer for a witness block. `RwMap::default()` is empty, without
+/// any `Start` padding row; use [`RwMap::new`] for a map with one.
 #[derive(Debug, Default, Clone)]
 pub struct RwMap(pub HashMap<Target, Vec<Rw>>);
 
@@ -24,19 +36,94 @@ impl std::ops::Index<(Target, usize)> for RwMap {
     type Output = Rw;
 
     fn index(&self, (tag, idx): (Target, usize)) -> &Self::Output {
-        &self.0.get(&tag).unwrap()[idx]
+        self.try_get(tag, idx)
+            .unwrap_or_else(|| panic!("RwMap has no {:?} row {}", tag, idx))
     }
 }
 
 impl std::ops::Index<OperationRef> for RwMap {
     type Output = Rw;
 
-    fn index(&self, OperationRef(tag, idx): OperationRef) -> &Self::Output {
-        &self.0.get(&tag).unwrap()[idx]
+    fn index(&self, op: OperationRef) -> &Self::Output {
+        self.get_op(op)
+            .unwrap_or_else(|| panic!("RwMap has no row for {:?}", op))
+    }
+}
+
+/// Serialized as the rows of [`RwMap::table_assignments`], so the buckets are
+/// rebuilt from the `tag` of each row on deserialization.
+#[cfg(feature = "rw-serde")]
+impl serde::Serialize for RwMap {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.table_assignments())
+    }
+}
+
+#[cfg(feature = "rw-serde")]
+impl<'de> serde::Deserialize<'de> for RwMap {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = <Vec<Rw> as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(Self::from_rows(rows))
     }
 }
 
 impl RwMap {
+    /// An empty map holding a single `Rw::Start { rw_counter: 1 }` padding
+    /// row, unlike `RwMap::default()`.
+    pub fn new() -> Self {
+        Self(HashMap::from([(
+            Target::Start,
+            vec![Rw::Start {
+                rw_counter: RwCounter(1),
+            }],
+        )]))
+    }
+    /// Build the map from the container, converting only the operations of
+    /// `targets`. The other targets are skipped entirely, so the rw_counters
+    /// of the resulting map are not continuous and
+    /// [`Self::check_rw_counter_sanity`] is not expected to hold.
+    pub fn from_container_targets(
+        container: &operation::OperationContainer,
+        targets: &[Target],
+    ) -> Self {
+        Self(
+            targets
+                .iter()
+                .map(|&target| (target, container_rows(container, target)))
+                .collect(),
+        )
+    }
+    /// Highest rw_counter of the map, ignoring the Start padding rows, or 0
+    /// if the map has no other rows.
+    pub fn max_rw_counter(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(tag, _rs)| !matches!(tag, Target::Start))
+            .flat_map(|(_tag, rs)| rs)
+            .map(|r| r.rw_counter().0)
+            .max()
+            .unwrap_or_default()
+    }
+    /// The rows of all targets whose rw_counter is in `range`, e.g. the
+    /// accesses of a single step, sorted by rw_counter.
+    pub fn rows_in_rwc_range(&self, range: Range<usize>) -> Vec<Rw> {
+        self.0
+            .values()
+            .flatten()
+            .filter(|row| range.contains(&row.rw_counter().0))
+            .copied()
+            .sorted_by_key(|row| row.rw_counter())
+            .collect()
+    }
+    /// Number of rows of each target, ordered by target. Targets without
+    /// rows are omitted.
+    pub fn tag_counts(&self) -> BTreeMap<Target, usize> {
+        self.0
+            .iter()
+            .filter(|(_tag, rs)| !rs.is_empty())
+            .map(|(tag, rs)| (*tag, rs.len()))
+            .collect()
+    }
     /// Check rw_counter is continuous and starting from 1
     pub fn check_rw_counter_sanity(&self) {
         for (idx, rw_counter) in self
@@ -48,64 +135,76 @@ impl RwMap {
             .sorted()
             .enumerate()
         {
-            debug_assert_eq!(idx, rw_counter - 1);
+            debug_assert_eq!(RwCounter(idx + 1), rw_counter);
         }
     }
-    /// Check value in the same way like StateCircuit
+    /// Check value in the same way like StateCircuit. The errors are only
+    /// logged; use [`Self::check_value_with`] to get them back.
     pub fn check_value(&self) {
-        let err_msg_first = "first access reads don't change value";
-        let err_msg_non_first = "non-first access reads don't change value";
-        let rows = self.table_assignments();
-        let updates = MptUpdates::mock_from(&rows);
-        let mut errs = Vec::new();
-        for idx in 1..rows.len() {
-            let row = &rows[idx];
-            let prev_row = &rows[idx - 1];
-            let is_first = {
-                let key = |row: &Rw| {
-                    (
-                        row.tag() as u64,
-                        row.id().unwrap_or_default(),
-                        row.address().unwrap_or_default(),
-                        row.field_tag().unwrap_or_default(),
-                        row.storage_key().unwrap_or_default(),
-                    )
-                };
-                key(prev_row) != key(row)
-            };
-            if !row.is_write() {
-                let value = row.value_assignment();
-                if is_first {
-                    // value == init_value
-                    let init_value = updates
-                        .get(row)
-                        .map(|u| u.value_assignments().1)
-                        .unwrap_or_default();
-                    if value != init_value {
-                        errs.push((idx, err_msg_first, *row, *prev_row));
-                    }
-                } else {
-                    // value == prev_value
-                    let prev_value = prev_row.value_assignment();
-
-                    if value != prev_value {
-                        errs.push((idx, err_msg_non_first, *row, *prev_row));
-                    }
-                }
-            }
+        _ = self.check_value_with(CheckOptions::default());
+    }
+    /// Same as [`Self::check_value`], returning the errors and logging them
+    /// only if `options.log` is set, e.g. to keep tests that check many
+    /// invalid maps quiet.
+    #[must_use]
+    pub fn check_value_with(&self, options: CheckOptions) -> Vec<ValueError> {
+        let mut errs = self.value_errors();
+        if let Some(max_errors) = options.max_errors {
+            errs.truncate(max_errors);
         }
-        if !errs.is_empty() {
+        if options.log && !errs.is_empty() {
             log::error!("after rw value check, err num: {}", errs.len());
-            for (idx, err_msg, row, prev_row) in errs {
+            for err in errs.iter() {
                 log::error!(
                     "err: rw idx: {}, reason: \"{}\", row: {:?}, prev_row: {:?}",
-                    idx,
-                    err_msg,
-                    row,
-                    prev_row
+                    err.idx,
+                    err.reason,
+                    err.row,
+                    err.prev_row
                 );
             }
         }
+        errs
+    }
+    /// The reads found by [`Self::check_value`] whose value is not the value
+    /// of the previous access to the same key, or the initial value for first
+    /// accesses.
+    pub fn value_errors(&self) -> Vec<ValueError> {
+        let sorted = self.sorted();
+        let updates = MptUpdates::mock_from(sorted.rows());
+        sorted
+            .indexed_groups()
+            .flat_map(|(start, group)| group_value_errors(sorted.rows(), start, group, &updates))
+            .collect()
+    }
+    /// Same as [`Self::check_value`] without logging, e.g. to assert
+    /// `rw_map.verify_value().is_ok()` in tests.
+    pub fn verify_value(&self) -> Result<(), Vec<RwValueError>> {
+        let errs = self.value_errors();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Same as [`Self::value_errors`], checking every group of rows sharing
+    /// a key on its own rayon task, since the checks never cross groups.
+    #[cfg(feature = "parallel")]
+    pub fn par_value_errors(&self) -> Vec<ValueError> {
+        use rayon::prelude::*;
+
+        let sorted = self.sorted();
+        let updates = MptUpdates::mock_from(sorted.rows());
+        let groups: Vec<(usize, &[Rw])> = sorted.indexed_groups().collect();
+
+        let mut errs: Vec<ValueError> = groups
+            .par_iter()
+            .flat_map_iter(|&(start, group)| {
+                group_value_errors(sorted.rows(), start, group, &updates)
+            })
+            .collect();
+        errs.sort_by_key(|err| err.idx);
+        errs
     }
     /// Calculates the number of Rw::Start rows needed.
     /// `target_len` is allowed to be 0 as an "auto" mode,
@@ -123,33 +222,2363 @@ impl RwMap {
             1
         }
     }
+    /// Skip the leading Rw::Start padding rows of sorted rows
+    fn strip_padding(rows: &[Rw]) -> impl Iterator<Item = &Rw> {
+        rows.iter().skip_while(|rw| rw.is_padding_or_start())
+    }
+    /// Build Rws for assignment without any Rw::Start padding, the inverse of
+    /// [`Self::table_assignments_prepad`]
+    pub fn real_rows(&self) -> Vec<Rw> {
+        Self::strip_padding(&self.table_assignments())
+            .cloned()
+            .collect()
+    }
     /// Prepad Rw::Start rows to target length
     pub fn table_assignments_prepad(rows: &[Rw], target_len: usize) -> (Vec<Rw>, usize) {
         // Remove Start rows as we will add them from scratch.
-        let rows: Vec<Rw> = rows
-            .iter()
-            .skip_while(|rw| matches!(rw, Rw::Start { .. }))
-            .cloned()
-            .collect();
+        let rows: Vec<Rw> = Self::strip_padding(rows).cloned().collect();
         let padding_length = Self::padding_len(rows.len(), target_len);
-        let padding = (1..=padding_length).map(|rw_counter| Rw::Start { rw_counter });
+        let padding = (1..=padding_length).map(|rw_counter| Rw::Start {
+            rw_counter: RwCounter(rw_counter),
+        });
         (padding.chain(rows.into_iter()).collect(), padding_length)
     }
+    /// Resolve a MockProver-style `(row_offset, column)` coordinate of the
+    /// RwTable, padded to `target_len`, back to the `Rw` that was assigned at
+    /// that row and the value expected in that column. Returns `None` if the
+    /// offset is out of the table, the column name is unknown or the rows
+    /// don't fit in a non-zero `target_len`.
+    pub fn explain_cell<F: Field>(
+        &self,
+        row_offset: usize,
+        column: &str,
+        target_len: usize,
+    ) -> Option<(Rw, F)> {
+        let col = RW_ROW_COLUMN_NAMES
+            .iter()
+            .position(|name| *name == column)?;
+        let rows = self.table_assignments();
+        if target_len != 0 && Self::strip_padding(&rows).count() >= target_len {
+            return None;
+        }
+        let (rows, _) = Self::table_assignments_prepad(&rows, target_len);
+        let row = *rows.get(row_offset)?;
+        let value = row.table_assignment::<F>().unwrap().values()[col];
+        Some((row, value))
+    }
     /// Build Rws for assignment
     pub fn table_assignments(&self) -> Vec<Rw> {
         let mut rows: Vec<Rw> = self.0.values().flatten().cloned().collect();
-        rows.sort_by_key(|row| {
-            (
-                row.tag() as u64,
-                row.id().unwrap_or_default(),
-                row.address().unwrap_or_default(),
-                row.field_tag().unwrap_or_default(),
-                row.storage_key().unwrap_or_default(),
-                row.rw_counter(),
-            )
-        });
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            rows.par_sort_by_key(sort_key);
+        }
+        #[cfg(not(feature = "parallel"))]
+        rows.sort_by_key(sort_key);
         rows
     }
+    /// The rows of [`Self::table_assignments`], kept to be walked group by
+    /// group through [`SortedRwMap::sorted_groups`].
+    pub fn sorted(&self) -> SortedRwMap {
+        SortedRwMap(self.table_assignments())
+    }
+    /// Fold over all rows in the table assignment order of
+    /// [`Self::table_assignments`], to express one-pass aggregates over the
+    /// whole map.
+    pub fn fold_rows<B>(&self, init: B, f: impl FnMut(B, &Rw) -> B) -> B {
+        self.table_assignments().iter().fold(init, f)
+    }
+    /// Split the sorted rows into at most `k` contiguous chunks of roughly
+    /// equal size. Chunk boundaries are snapped forward to the next
+    /// [`RwKey`] group edge so that no group is split across chunks, which
+    /// may result in fewer than `k` chunks when groups are large.
+    pub fn chunk_sorted(&self, k: usize) -> Vec<Vec<Rw>> {
+        assert!(k > 0, "RwMap::chunk_sorted needs at least one chunk");
+        let sorted = self.sorted();
+        let chunk_len = (sorted.rows().len() + k - 1) / k;
+        let mut chunks = Vec::with_capacity(k);
+        let mut chunk = Vec::with_capacity(chunk_len);
+        for (_, group) in sorted.sorted_groups() {
+            if chunk.len() >= chunk_len {
+                chunks.push(std::mem::take(&mut chunk));
+            }
+            chunk.extend_from_slice(group);
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+    /// Check that every write to a key with a value_prev (the reversible
+    /// targets, Account and AccountStorage) has its value_prev equal to the
+    /// value of the previous access to the same key, which is the write side
+    /// counterpart of [`Self::check_value`]. Only the first break of each
+    /// [`RwKey`] group is reported.
+    pub fn check_value_prev_chain(&self) -> Result<(), Vec<PrevChainError>> {
+        let mut errs: Vec<PrevChainError> = Vec::new();
+        for (start, group) in self.sorted().indexed_groups() {
+            let first_break = group.windows(2).position(|pair| {
+                let (prev_row, row) = (&pair[0], &pair[1]);
+                row.is_write()
+                    && row.value_prev_assignment().map_or(false, |value_prev| {
+                        value_prev != prev_row.value_assignment()
+                    })
+            });
+            if let Some(offset) = first_break {
+                errs.push(PrevChainError {
+                    idx: start + offset + 1,
+                    row: group[offset + 1],
+                    prev_row: group[offset],
+                });
+            }
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Collect the code hashes referenced by the Account CodeHash rows (both
+    /// value and value_prev) and the CallContext CodeHash rows, i.e. the
+    /// bytecodes that must be included in the bytecode table. Unless
+    /// `include_empty` is set, the zero hash of non-existing accounts and the
+    /// hash of the empty code are left out.
+    pub fn referenced_code_hashes(&self, include_empty: bool) -> BTreeSet<Word> {
+        let empty_code_hash = CodeDB::empty_code_hash().to_word();
+        self.0
+            .values()
+            .flatten()
+            .flat_map(|row| match row {
+                Rw::Account {
+                    field_tag: AccountFieldTag::CodeHash,
+                    value,
+                    value_prev,
+                    ..
+                } => vec![*value, *value_prev],
+                Rw::CallContext {
+                    field_tag: CallContextFieldTag::CodeHash,
+                    value,
+                    ..
+                } => vec![*value],
+                _ => vec![],
+            })
+            .filter(|hash| include_empty || !(hash.is_zero() || *hash == empty_code_hash))
+            .collect()
+    }
+    /// The RLC of every row of the sorted table, padded in the "auto" mode of
+    /// [`Self::table_assignments_prepad`] with a single Rw::Start row. As a
+    /// multiset this is what the permutation argument between the EVM circuit
+    /// and the State circuit compares, so it can be checked out of circuit.
+    pub fn rlc_multiset<F: Field>(&self, randomness: F) -> Vec<F> {
+        let (rows, _) = Self::table_assignments_prepad(&self.table_assignments(), 0);
+        rows.iter()
+            .map(|row| row.table_assignment::<F>().unwrap().rlc(randomness))
+            .collect()
+    }
+    /// Check that the first access to every stack slot of a call is a write,
+    /// since the EVM never pops a slot it hasn't pushed. Returns the first read
+    /// of each slot that happens before any write to it.
+    pub fn check_stack_init(&self) -> Result<(), Vec<StackInitError>> {
+        let stack = RwMap::from_rows(self.0.get(&Target::Stack).cloned().unwrap_or_default());
+        let mut errs: Vec<StackInitError> = Vec::new();
+        for (_, group) in stack.sorted().sorted_groups() {
+            let row = &group[0];
+            if let Rw::Stack {
+                is_write: false,
+                call_id,
+                stack_pointer,
+                ..
+            } = *row
+            {
+                errs.push(StackInitError {
+                    call_id,
+                    stack_pointer,
+                    row: *row,
+                });
+            }
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Split the rows into execution steps, each sorted by rw_counter, so the
+    /// EVM circuit can assign one step's worth of rows at a time.
+    /// `step_boundaries` must be sorted: step `i` holds the rows with
+    /// `step_boundaries[i - 1] <= rw_counter < step_boundaries[i]`, so `n`
+    /// boundaries yield `n + 1` steps. `Start` rows are left out, as in
+    /// [`Self::index_by_step`].
+    pub fn chunks_by_step(&self, step_boundaries: &[usize]) -> Vec<Vec<Rw>> {
+        debug_assert!(
+            step_boundaries.windows(2).all(|w| w[0] <= w[1]),
+            "step boundaries must be sorted"
+        );
+        let mut steps = vec![Vec::new(); step_boundaries.len() + 1];
+        for row in self
+            .0
+            .values()
+            .flatten()
+            .filter(|row| !row.is_padding_or_start())
+            .sorted_by_key(|row| row.rw_counter())
+        {
+            let step = step_boundaries.partition_point(|&boundary| boundary <= row.rw_counter().0);
+            steps[step].push(*row);
+        }
+        steps
+    }
+    /// Build a RwMap from the container like the `From` impl does, but bail
+    /// out before allocating any rows when the container holds more than
+    /// `max_rows` operations.
+    pub fn try_from_container_limited(
+        container: &operation::OperationContainer,
+        max_rows: usize,
+    ) -> Result<Self, TooManyRows> {
+        let rows = container_len(container);
+        if rows > max_rows {
+            return Err(TooManyRows { rows, max_rows });
+        }
+        Ok(Self::from(container))
+    }
+    /// The final nonce, balance and code hash of every account accessed in the
+    /// block, each taken from the highest-counter write to that field, or the
+    /// last read if the field is never written.
+    pub fn final_account_state(&self) -> BTreeMap<Address, AccountSummary> {
+        let mut accounts: BTreeMap<Address, AccountSummary> = BTreeMap::new();
+        // Reads are visited before writes so that any write overrides them.
+        for row in self
+            .0
+            .get(&Target::Account)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .sorted_by_key(|row| (row.is_write(), row.rw_counter()))
+        {
+            if let Rw::Account {
+                account_address,
+                field_tag,
+                value,
+                ..
+            } = *row
+            {
+                let summary = accounts.entry(account_address).or_default();
+                match field_tag {
+                    AccountFieldTag::Nonce => summary.nonce = Some(value),
+                    AccountFieldTag::Balance => summary.balance = Some(value),
+                    AccountFieldTag::CodeHash => summary.code_hash = Some(value),
+                    AccountFieldTag::NonExisting => {}
+                }
+            }
+        }
+        accounts
+    }
+    /// A copy of the map keeping only the write rows in each bucket, e.g. to
+    /// compute the state delta of a block. `Start` rows are never writes so
+    /// they are dropped too. The rw_counters of the result are no longer
+    /// contiguous, so it won't pass [`Self::check_rw_counter_sanity`].
+    pub fn writes_only(&self) -> RwMap {
+        Self(
+            self.0
+                .iter()
+                .map(|(target, rows)| {
+                    (
+                        *target,
+                        rows.iter().filter(|row| row.is_write()).copied().collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+    /// The rw_counter of the `n`-th access (0 is the first) to `key` in counter
+    /// order, or `None` if the key has fewer than `n + 1` accesses.
+    pub fn nth_access_counter(&self, key: RwKey, n: usize) -> Option<usize> {
+        self.0
+            .get(&key.tag)?
+            .iter()
+            .filter(|row| row.key() == key)
+            .map(|row| row.rw_counter().0)
+            .sorted()
+            .nth(n)
+    }
+    /// The value of `key` right after `rw_counter`, i.e. the value of the
+    /// highest-counter access with a counter not greater than `rw_counter`.
+    /// If every access comes later, the initial value is returned instead:
+    /// the committed value for storage, or the value_prev of the first
+    /// access for the other targets that have one. Returns `None` if the key
+    /// is never accessed or its initial value is unknown.
+    pub fn value_at(&self, key: RwKey, rw_counter: usize) -> Option<Word> {
+        let rows = self
+            .0
+            .get(&key.tag)?
+            .iter()
+            .filter(|row| row.key() == key)
+            .sorted_by_key(|row| row.rw_counter())
+            .collect::<Vec<_>>();
+        match rows
+            .iter()
+            .rev()
+            .find(|row| row.rw_counter() <= RwCounter(rw_counter))
+        {
+            Some(row) => Some(row.value_assignment()),
+            None => rows.first().and_then(|row| {
+                row.committed_value()
+                    .or_else(|| row.value_prev_assignment())
+            }),
+        }
+    }
+    /// Check that the nonce writes of every account never decrease, and that
+    /// the value_prev of each write is the value of the previous nonce write
+    /// to the same account, in rw_counter order.
+    pub fn check_nonce_monotone(&self) -> Result<(), Vec<NonceError>> {
+        let writes = self
+            .0
+            .get(&Target::Account)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|row| {
+                row.is_write()
+                    && matches!(
+                        row,
+                        Rw::Account {
+                            field_tag: AccountFieldTag::Nonce,
+                            ..
+                        }
+                    )
+            })
+            .sorted_by_key(|row| (row.address(), row.rw_counter()));
+        let mut errs: Vec<NonceError> = Vec::new();
+        for (prev_row, row) in writes.tuple_windows() {
+            if let (
+                Rw::Account {
+                    account_address: prev_address,
+                    value: prev_value,
+                    ..
+                },
+                Rw::Account {
+                    account_address,
+                    value,
+                    value_prev,
+                    ..
+                },
+            ) = (*prev_row, *row)
+            {
+                if account_address == prev_address
+                    && (value < prev_value || value_prev != prev_value)
+                {
+                    errs.push(NonceError {
+                        account_address,
+                        row: *row,
+                        prev_value,
+                    });
+                }
+            }
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Split the rw_counter space into windows of `bucket` counters starting
+    /// at 1, and list the targets accessed in each window up to the last
+    /// counter. `Start` rows are skipped as they are not part of the execution.
+    pub fn timeline(&self, bucket: usize) -> Vec<(RangeInclusive<usize>, BTreeSet<Target>)> {
+        assert!(bucket > 0, "timeline bucket size must be positive");
+        let mut windows: Vec<BTreeSet<Target>> = Vec::new();
+        for row in self.0.values().flatten() {
+            if matches!(row, Rw::Start { .. }) {
+                continue;
+            }
+            let idx = row.rw_counter().0.saturating_sub(1) / bucket;
+            if windows.len() <= idx {
+                windows.resize(idx + 1, BTreeSet::new());
+            }
+            windows[idx].insert(row.tag());
+        }
+        windows
+            .into_iter()
+            .enumerate()
+            .map(|(idx, targets)| (idx * bucket + 1..=(idx + 1) * bucket, targets))
+            .collect()
+    }
+    /// Sort every bucket in place in the order of [`Self::table_assignments`],
+    /// so that [`Self::table_assignments_presorted`] can merge them instead of
+    /// sorting all rows again.
+    pub fn sort_buckets(&mut self) {
+        for rows in self.0.values_mut() {
+            rows.sort_by_key(sort_key);
+        }
+    }
+    /// Same as [`Self::table_assignments`] but assumes the buckets are already
+    /// sorted by [`Self::sort_buckets`], and only merges them.
+    pub fn table_assignments_presorted(&self) -> Vec<Rw> {
+        merge_sorted(self.0.values().cloned().collect())
+    }
+    /// Bucket `rows` by target, Start rows included, e.g. to rebuild the map
+    /// from its [`Self::table_assignments`]. The rows are not checked.
+    pub fn from_rows(rows: impl IntoIterator<Item = Rw>) -> RwMap {
+        let mut rws: HashMap<Target, Vec<Rw>> = HashMap::new();
+        for row in rows {
+            rws.entry(row.tag()).or_default().push(row);
+        }
+        Self(rws)
+    }
+    /// Bucket `rows` by target and check that the non-`Start` rw_counters are
+    /// unique and contiguous from 1, as [`Self::check_rw_counter_sanity`]
+    /// expects, and that no two rows share the same sort key.
+    pub fn from_rows_validated(rows: Vec<Rw>) -> Result<RwMap, RwBuildError> {
+        let counters = rows
+            .iter()
+            .filter(|row| !matches!(row, Rw::Start { .. }))
+            .map(|row| row.rw_counter().0)
+            .sorted()
+            .collect::<Vec<_>>();
+        for (idx, rw_counter) in counters.iter().enumerate() {
+            if idx > 0 && counters[idx - 1] == *rw_counter {
+                return Err(RwBuildError::DuplicateRwCounter(*rw_counter));
+            }
+            if *rw_counter != idx + 1 {
+                return Err(RwBuildError::MissingRwCounter(idx + 1));
+            }
+        }
+        if let Some((key, rw_counter)) = rows
+            .iter()
+            .map(|row| (row.key(), row.rw_counter().0))
+            .sorted()
+            .tuple_windows()
+            .find(|(a, b)| a == b)
+            .map(|(a, _)| a)
+        {
+            return Err(RwBuildError::DuplicateSortKey { key, rw_counter });
+        }
+        Ok(Self::from_rows(rows))
+    }
+    /// Number of distinct `(account_address, storage_key)` slots accessed by
+    /// the `AccountStorage` rows of transaction `tx_id`.
+    pub fn distinct_storage_slots(&self, tx_id: usize) -> usize {
+        self.storage_slots(|row_tx_id| row_tx_id == tx_id).len()
+    }
+    /// Number of distinct `(account_address, storage_key)` slots accessed by
+    /// the `AccountStorage` rows of the whole block.
+    pub fn distinct_storage_slots_all(&self) -> usize {
+        self.storage_slots_touched().len()
+    }
+    /// The distinct `(account_address, storage_key)` slots accessed by the
+    /// `AccountStorage` rows of the whole block, ordered, e.g. to build the
+    /// MPT updates reproducibly.
+    pub fn storage_slots_touched(&self) -> BTreeSet<(Address, Word)> {
+        self.storage_slots(|_| true)
+    }
+    /// The fields touched by the `Account` rows of the whole block, per
+    /// account, e.g. to decide which account leaves need MPT proofs.
+    pub fn accounts_touched(&self) -> BTreeMap<Address, BTreeSet<AccountFieldTag>> {
+        let mut accounts: BTreeMap<Address, BTreeSet<AccountFieldTag>> = BTreeMap::new();
+        for row in self
+            .0
+            .get(&Target::Account)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Rw::Account {
+                account_address,
+                field_tag,
+                ..
+            } = *row
+            {
+                accounts
+                    .entry(account_address)
+                    .or_default()
+                    .insert(field_tag);
+            }
+        }
+        accounts
+    }
+    fn storage_slots(
+        &self,
+        mut filter_tx_id: impl FnMut(usize) -> bool,
+    ) -> BTreeSet<(Address, Word)> {
+        self.0
+            .get(&Target::Storage)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match row {
+                Rw::AccountStorage {
+                    account_address,
+                    storage_key,
+                    tx_id,
+                    ..
+                } if filter_tx_id(*tx_id) => Some((*account_address, *storage_key)),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Dump the sorted rows one per line as
+    /// `tag:rwc:w:id:addr:field:key:value:value_prev:init_val` with fixed
+    /// width hex fields, to attach to bug reports. The init_val column is
+    /// needed to restore the committed value of storage rows.
+    /// [`Self::from_hex_dump`] parses it back.
+    pub fn to_hex_dump(&self) -> String {
+        const WIDTHS: [usize; 10] = [2, 8, 1, 8, 40, 2, 64, 64, 64, 64];
+        self.table_assignments()
+            .iter()
+            .map(|row| {
+                dump_columns(row)
+                    .iter()
+                    .zip(WIDTHS)
+                    .map(|(value, width)| format!("{:0>width$}", format!("{value:x}")))
+                    .join(":")
+                    + "\n"
+            })
+            .collect()
+    }
+    /// Parse a dump produced by [`Self::to_hex_dump`]. Empty lines are
+    /// skipped.
+    pub fn from_hex_dump(s: &str) -> Result<RwMap, ParseError> {
+        let mut rws: HashMap<Target, Vec<Rw>> = HashMap::new();
+        for (line, text) in s.lines().enumerate() {
+            if text.trim().is_empty() {
+                continue;
+            }
+            let row = parse_hex_dump_row(text.trim()).map_err(|message| ParseError {
+                line: line + 1,
+                message,
+            })?;
+            rws.entry(row.tag()).or_default().push(row);
+        }
+        Ok(Self(rws))
+    }
+    /// Assign rw_counters 1..=n to the rows following the execution order
+    /// given as the key of each access. Accesses to the same key take the
+    /// rows of that key in their bucket order. `Start` rows keep their
+    /// counters.
+    ///
+    /// Panics if `order` doesn't cover every non-`Start` row exactly once.
+    pub fn assign_sequential_counters(&mut self, order: &[RwKey]) {
+        let mut pending: HashMap<RwKey, VecDeque<(Target, usize)>> = HashMap::new();
+        for (target, rows) in self.0.iter() {
+            if *target == Target::Start {
+                continue;
+            }
+            for (idx, row) in rows.iter().enumerate() {
+                pending
+                    .entry(row.key())
+                    .or_default()
+                    .push_back((*target, idx));
+            }
+        }
+        for (idx, key) in order.iter().enumerate() {
+            let (target, row_idx) = pending
+                .get_mut(key)
+                .and_then(VecDeque::pop_front)
+                .unwrap_or_else(|| panic!("no row left for access {idx} to {key:?}"));
+            let row = &mut self.0.get_mut(&target).unwrap()[row_idx];
+            *row = row.with_rw_counter(RwCounter(idx + 1));
+        }
+        assert!(
+            pending.values().all(VecDeque::is_empty),
+            "execution order doesn't cover every row"
+        );
+    }
+    /// The net storage changes of the block against the `pre` state, as
+    /// `(pre_value, post_value)` for every written slot whose final value
+    /// differs from its value in `pre`. Slots missing in `pre` are zero.
+    pub fn storage_changes(
+        &self,
+        pre: &BTreeMap<(Address, Word), Word>,
+    ) -> BTreeMap<(Address, Word), (Word, Word)> {
+        let mut post: BTreeMap<(Address, Word), Word> = BTreeMap::new();
+        for row in self
+            .0
+            .get(&Target::Storage)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|row| row.is_write())
+            .sorted_by_key(|row| row.rw_counter())
+        {
+            if let Rw::AccountStorage {
+                account_address,
+                storage_key,
+                value,
+                ..
+            } = *row
+            {
+                post.insert((account_address, storage_key), value);
+            }
+        }
+        post.into_iter()
+            .filter_map(|(slot, post_value)| {
+                let pre_value = pre.get(&slot).copied().unwrap_or_default();
+                (pre_value != post_value).then_some((slot, (pre_value, post_value)))
+            })
+            .collect()
+    }
+    /// Check that the rw_counter ranges spanned by the tx-scoped rows of each
+    /// transaction (the rows with a tx_id) are disjoint, so that every
+    /// transaction owns a contiguous sub-range of counters. Each pair of
+    /// transactions with overlapping ranges is reported once.
+    pub fn check_contiguous_per_tx(&self) -> Result<(), Vec<TxCounterError>> {
+        let mut ranges: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+        for row in self.0.values().flatten() {
+            let tx_id = match row {
+                Rw::AccountStorage { tx_id, .. }
+                | Rw::TxAccessListAccount { tx_id, .. }
+                | Rw::TxAccessListAccountStorage { tx_id, .. }
+                | Rw::TxRefund { tx_id, .. }
+                | Rw::TxLog { tx_id, .. }
+                | Rw::TxReceipt { tx_id, .. } => *tx_id,
+                _ => continue,
+            };
+            let rw_counter = row.rw_counter().0;
+            let range = ranges.entry(tx_id).or_insert((rw_counter, rw_counter));
+            range.0 = range.0.min(rw_counter);
+            range.1 = range.1.max(rw_counter);
+        }
+        let errs: Vec<TxCounterError> = ranges
+            .iter()
+            .tuple_combinations()
+            .filter(|((_, a), (_, b))| a.0 <= b.1 && b.0 <= a.1)
+            .map(|((tx_id, a), (other_tx_id, b))| TxCounterError {
+                tx_id: *tx_id,
+                range: a.0..=a.1,
+                other_tx_id: *other_tx_id,
+                other_range: b.0..=b.1,
+            })
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Shift the rw_counter of every row, `Start` rows included, by `offset`,
+    /// e.g. to place the rows of a chunk after the ones of previous chunks.
+    /// Use [`Self::renumber_start_rows`] afterwards to move the `Start` rows
+    /// back to the front of the counters.
+    pub fn offset_rw_counters(&mut self, offset: usize) {
+        for row in self.0.values_mut().flatten() {
+            *row = row.with_rw_counter(row.rw_counter() + offset);
+        }
+    }
+    /// Renumber the `Start` rows as `1..=n` in their current counter order,
+    /// which [`Self::table_assignments_prepad`] assumes of padding rows. This
+    /// undoes the shift of the `Start` rows by [`Self::offset_rw_counters`],
+    /// and collapses the duplicate or gapped `Start` counters left by merging
+    /// maps so that [`Self::check_rw_counter_sanity`] holds again. The other
+    /// rows are left untouched.
+    pub fn renumber_start_rows(&mut self) {
+        if let Some(rows) = self.0.get_mut(&Target::Start) {
+            rows.sort_by_key(|row| row.rw_counter());
+            for (idx, row) in rows.iter_mut().enumerate() {
+                *row = row.with_rw_counter(RwCounter(idx + 1));
+            }
+        }
+    }
+    /// Rough estimate of the heap memory held by the map: the capacity of
+    /// every bucket times the size of a row, plus the map's own slots. `Rw`
+    /// holds no heap data, so this covers all the rows.
+    pub fn heap_bytes(&self) -> usize {
+        let slot_bytes = std::mem::size_of::<(Target, Vec<Rw>)>() + 1;
+        let rows_bytes: usize = self
+            .0
+            .values()
+            .map(|rows| rows.capacity() * std::mem::size_of::<Rw>())
+            .sum();
+        self.0.capacity() * slot_bytes + rows_bytes
+    }
+    /// Check that within each transaction the log_ids of the `TxLog` rows,
+    /// taken in the order the logs are emitted (the first rw_counter of each
+    /// log), increase by one from each log to the next. Reports every log
+    /// that doesn't follow the previous one of its transaction.
+    pub fn check_log_id_sequence(&self) -> Result<(), Vec<LogIdError>> {
+        let mut first_counters: BTreeMap<(usize, u64), usize> = BTreeMap::new();
+        for row in self
+            .0
+            .get(&Target::TxLog)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Rw::TxLog {
+                rw_counter,
+                tx_id,
+                log_id,
+                ..
+            } = *row
+            {
+                let first = first_counters
+                    .entry((tx_id, log_id))
+                    .or_insert(rw_counter.0);
+                *first = (*first).min(rw_counter.0);
+            }
+        }
+        let errs: Vec<LogIdError> = first_counters
+            .into_iter()
+            .map(|((tx_id, log_id), rw_counter)| (tx_id, rw_counter, log_id))
+            .sorted()
+            .tuple_windows()
+            .filter(|((prev_tx_id, _, prev_log_id), (tx_id, _, log_id))| {
+                prev_tx_id == tx_id && *log_id != prev_log_id + 1
+            })
+            .map(|((_, _, prev_log_id), (tx_id, _, log_id))| LogIdError {
+                tx_id,
+                log_id,
+                prev_log_id,
+            })
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// The rows keyed by rw_counter, iterating in the EVM circuit order.
+    /// `Start` rows are left out since their counters are only used for
+    /// padding. Fails if two rows share a counter.
+    pub fn by_counter(&self) -> Result<BTreeMap<usize, Rw>, RwBuildError> {
+        let mut rows = BTreeMap::new();
+        for row in self
+            .0
+            .iter()
+            .filter(|(tag, _)| !matches!(tag, Target::Start))
+            .flat_map(|(_, rows)| rows)
+        {
+            let rw_counter = row.rw_counter().0;
+            if rows.insert(rw_counter, *row).is_some() {
+                return Err(RwBuildError::DuplicateRwCounter(rw_counter));
+            }
+        }
+        Ok(rows)
+    }
+    /// Number of `Account` accesses to `field`, only counting reads or writes
+    /// if `is_write` is set. E.g. `Balance` reads hint at BALANCE and
+    /// SELFBALANCE.
+    pub fn count_account_field_accesses(
+        &self,
+        field: AccountFieldTag,
+        is_write: Option<bool>,
+    ) -> usize {
+        self.count_field_accesses(Target::Account, field as u64, is_write)
+    }
+    /// Number of `CallContext` accesses to `field`, only counting reads or
+    /// writes if `is_write` is set.
+    pub fn count_call_context_accesses(
+        &self,
+        field: CallContextFieldTag,
+        is_write: Option<bool>,
+    ) -> usize {
+        self.count_field_accesses(Target::CallContext, field as u64, is_write)
+    }
+    fn count_field_accesses(&self, target: Target, field: u64, is_write: Option<bool>) -> usize {
+        self.0
+            .get(&target)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|row| {
+                row.field_tag() == Some(field)
+                    && is_write.map_or(true, |is_write| row.is_write() == is_write)
+            })
+            .count()
+    }
+    /// The sorted rows padded to `target_len` (or with a single padding row
+    /// if `target_len` is 0), which always start with at least one `Start`
+    /// row, even for an empty map. This is what the State circuit assigns.
+    pub fn assignments_nonempty(&self, target_len: usize) -> Vec<Rw> {
+        Self::table_assignments_prepad(&self.table_assignments(), target_len).0
+    }
+    /// Check that the `CallContext` values of the bounded fields are within
+    /// their range: `Depth` up to 1024, `StackPointer` below 1024, addresses
+    /// in 20 bytes, memory offsets and lengths in a u64, and flags in a bool.
+    /// `StackPointer` deliberately departs from the requested `<= 1024`: the
+    /// pointer saved at a call already counts the pushed result, and it is
+    /// bounded like the 10-bit address of a `Stack` row.
+    pub fn check_call_context_ranges(&self) -> Result<(), Vec<CallContextRangeError>> {
+        let errs: Vec<CallContextRangeError> = self
+            .0
+            .get(&Target::CallContext)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::CallContext {
+                    field_tag, value, ..
+                } => {
+                    let max = call_context_max(field_tag)?;
+                    (value > max).then_some(CallContextRangeError {
+                        row: *row,
+                        field_tag,
+                        max,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Serialize the sorted rows in a compact binary form where the account
+    /// addresses are interned: they are stored once in a dictionary and rows
+    /// refer to them by index. The other addresses, e.g. the packed TxLog
+    /// address, are stored as words. Words are stored without their leading
+    /// zero bytes. [`Self::from_interned_binary`] parses it back.
+    pub fn to_interned_binary(&self) -> Vec<u8> {
+        self.to_binary(true)
+    }
+    /// Binary form of [`Self::to_interned_binary`], with the account addresses
+    /// stored inline in every row if `intern_addresses` is false.
+    fn to_binary(&self, intern_addresses: bool) -> Vec<u8> {
+        let rows = self.table_assignments();
+        let addresses: Vec<Address> = rows
+            .iter()
+            .filter(|row| intern_addresses && has_account_address(row.tag()))
+            .filter_map(Rw::address)
+            .unique()
+            .collect();
+        let address_index: HashMap<Address, u64> = addresses
+            .iter()
+            .enumerate()
+            .map(|(idx, address)| (*address, idx as u64))
+            .collect();
+
+        let mut bytes = Vec::new();
+        bytes.extend((addresses.len() as u32).to_le_bytes());
+        for address in addresses.iter() {
+            bytes.extend(address.as_bytes());
+        }
+        bytes.extend((rows.len() as u32).to_le_bytes());
+        for row in rows.iter() {
+            let columns = dump_columns(row);
+            bytes.push(columns[0].low_u64() as u8);
+            bytes.extend(columns[1].low_u64().to_le_bytes());
+            bytes.push(columns[2].low_u64() as u8);
+            bytes.extend(columns[3].low_u64().to_le_bytes());
+            match row.address() {
+                Some(address) if has_account_address(row.tag()) => {
+                    if intern_addresses {
+                        bytes.extend(address_index[&address].to_le_bytes());
+                    } else {
+                        bytes.extend(address.as_bytes());
+                    }
+                }
+                _ => push_binary_word(&mut bytes, &columns[4]),
+            }
+            bytes.push(columns[5].low_u64() as u8);
+            for word in columns[6..].iter() {
+                push_binary_word(&mut bytes, word);
+            }
+        }
+        bytes
+    }
+    /// Parse the output of [`Self::to_interned_binary`]. The `line` of the
+    /// returned error is the number of the row, starting from 1.
+    pub fn from_interned_binary(bytes: &[u8]) -> Result<RwMap, ParseError> {
+        let mut reader = DumpReader { bytes };
+        let header_error = |message| ParseError { line: 0, message };
+        let address_len = reader.u32().map_err(header_error)?;
+        let addresses = (0..address_len)
+            .map(|_| reader.take(20).map(Address::from_slice))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(header_error)?;
+        let rows_len = reader.u32().map_err(header_error)?;
+
+        let mut rws: HashMap<Target, Vec<Rw>> = HashMap::new();
+        for line in 1..=rows_len as usize {
+            let mut read_row = || {
+                let tag = reader.u8()?;
+                let rw_counter = reader.u64()?;
+                let is_write = reader.u8()?;
+                let id = reader.u64()?;
+                let address = if Target::iter()
+                    .any(|target| target as u8 == tag && has_account_address(target))
+                {
+                    let address = reader.u64()?;
+                    addresses
+                        .get(address as usize)
+                        .ok_or_else(|| format!("unknown address index {address}"))?
+                        .to_word()
+                } else {
+                    reader.word()?
+                };
+                let field_tag = reader.u8()?;
+                row_from_dump_columns([
+                    Word::from(tag as u64),
+                    Word::from(rw_counter),
+                    Word::from(is_write as u64),
+                    Word::from(id),
+                    address,
+                    Word::from(field_tag as u64),
+                    reader.word()?,
+                    reader.word()?,
+                    reader.word()?,
+                    reader.word()?,
+                ])
+            };
+            let row = read_row().map_err(|message| ParseError { line, message })?;
+            rws.entry(row.tag()).or_default().push(row);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(header_error(format!(
+                "{} trailing bytes",
+                reader.bytes.len()
+            )));
+        }
+        Ok(Self(rws))
+    }
+    /// Check that the first `TxAccessListAccount` access to each address in
+    /// a transaction finds it cold (EIP-2929), unless the address is in
+    /// `prewarmed`, which holds the precompiles and the addresses of the
+    /// transaction access list.
+    pub fn check_first_access_cold(
+        &self,
+        prewarmed: &BTreeSet<Address>,
+    ) -> Result<(), Vec<ColdAccessError>> {
+        let mut first_accesses: BTreeMap<(usize, Address), Rw> = BTreeMap::new();
+        for row in self
+            .0
+            .get(&Target::TxAccessListAccount)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Rw::TxAccessListAccount {
+                tx_id,
+                account_address,
+                ..
+            } = *row
+            {
+                let first = first_accesses
+                    .entry((tx_id, account_address))
+                    .or_insert(*row);
+                if row.rw_counter() < first.rw_counter() {
+                    *first = *row;
+                }
+            }
+        }
+        let errs: Vec<ColdAccessError> = first_accesses
+            .into_iter()
+            .filter(|((_, account_address), row)| {
+                !prewarmed.contains(account_address)
+                    && matches!(
+                        row,
+                        Rw::TxAccessListAccount {
+                            is_warm_prev: true,
+                            ..
+                        }
+                    )
+            })
+            .map(|((tx_id, account_address), row)| ColdAccessError {
+                tx_id,
+                account_address,
+                row,
+            })
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Turn the map into a [`RwMapBuilder`] to edit its rows.
+    pub fn into_builder(self) -> RwMapBuilder {
+        let (start_rows, rows): (Vec<Rw>, Vec<Rw>) = self
+            .0
+            .into_values()
+            .flatten()
+            .partition(|row| matches!(row, Rw::Start { .. }));
+        RwMapBuilder {
+            start_rows,
+            rows: rows.into_iter().sorted_by_key(Rw::rw_counter).collect(),
+        }
+    }
+    /// Split the rw_counters into `(depth, range)` segments of the call tree.
+    /// The depth of each call is the value of its `CallContext` `Depth` rows,
+    /// and the rows scoped to a call (`CallContext`, `Stack` and `Memory`) are
+    /// taken in counter order, starting a new segment whenever the depth of
+    /// their call changes. Each range spans from the first to the last row of
+    /// its segment. Rows of calls without a `Depth` row are skipped.
+    pub fn call_depth_segments(&self) -> Vec<(usize, RangeInclusive<usize>)> {
+        let depths: HashMap<usize, usize> = self
+            .0
+            .get(&Target::CallContext)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match row {
+                Rw::CallContext {
+                    call_id,
+                    field_tag: CallContextFieldTag::Depth,
+                    value,
+                    ..
+                } => Some((*call_id, value.as_usize())),
+                _ => None,
+            })
+            .collect();
+        let mut segments: Vec<(usize, RangeInclusive<usize>)> = Vec::new();
+        for row in [Target::CallContext, Target::Stack, Target::Memory]
+            .iter()
+            .filter_map(|target| self.0.get(target))
+            .flatten()
+            .sorted_by_key(|row| row.rw_counter())
+        {
+            let Some(depth) = row.id().and_then(|call_id| depths.get(&call_id)) else {
+                continue;
+            };
+            let rw_counter = row.rw_counter().0;
+            match segments.last_mut() {
+                Some((last_depth, range)) if last_depth == depth => {
+                    *range = *range.start()..=rw_counter;
+                }
+                _ => segments.push((*depth, rw_counter..=rw_counter)),
+            }
+        }
+        segments
+    }
+    /// Remove every `Start` row, e.g. stray ones left by an import. Padding
+    /// is not lost: [`Self::table_assignments_prepad`] adds fresh `Start`
+    /// rows from counter 1 anyway.
+    pub fn prune_start(&mut self) {
+        if let Some(rows) = self.0.get_mut(&Target::Start) {
+            rows.clear();
+        }
+    }
+    /// Check that every `Memory` row of a call addresses a byte within the
+    /// largest `MemorySize` (in 32-byte words) recorded in the `CallContext`
+    /// rows of that call. Calls without a `MemorySize` row are not checked.
+    pub fn check_memory_within_size(&self) -> Result<(), Vec<MemoryBoundsError>> {
+        let mut memory_sizes: HashMap<usize, u64> = HashMap::new();
+        for row in self
+            .0
+            .get(&Target::CallContext)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Rw::CallContext {
+                call_id,
+                field_tag: CallContextFieldTag::MemorySize,
+                value,
+                ..
+            } = *row
+            {
+                let memory_size = memory_sizes.entry(call_id).or_default();
+                *memory_size = (*memory_size).max(value.low_u64());
+            }
+        }
+        let errs: Vec<MemoryBoundsError> = self
+            .0
+            .get(&Target::Memory)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::Memory {
+                    call_id,
+                    memory_address,
+                    ..
+                } => {
+                    let memory_size = *memory_sizes.get(&call_id)?;
+                    (memory_address >= memory_size * 32).then_some(MemoryBoundsError {
+                        call_id,
+                        row: *row,
+                        memory_size,
+                    })
+                }
+                _ => None,
+            })
+            .sorted_by_key(|err| err.row.rw_counter())
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// A copy of the map with only the buckets of `targets`, e.g. to test one
+    /// circuit in isolation. `Start` rows are kept only if `Target::Start` is
+    /// listed.
+    pub fn subset(&self, targets: &[Target]) -> RwMap {
+        Self(
+            self.0
+                .iter()
+                .filter(|(target, _)| targets.contains(target))
+                .map(|(target, rows)| (*target, rows.clone()))
+                .collect(),
+        )
+    }
+    /// The first access of every `Account` and `AccountStorage` key group, in
+    /// the order consumed by the MPT circuit: by address, then account rows
+    /// before storage rows, then field tag and storage key.
+    pub fn mpt_circuit_rows(&self) -> Vec<Rw> {
+        let mut first_accesses: HashMap<RwKey, Rw> = HashMap::new();
+        for target in [Target::Account, Target::Storage] {
+            for row in self.0.get(&target).map(Vec::as_slice).unwrap_or_default() {
+                first_accesses
+                    .entry(row.key())
+                    .and_modify(|first| {
+                        if row.rw_counter() < first.rw_counter() {
+                            *first = *row;
+                        }
+                    })
+                    .or_insert(*row);
+            }
+        }
+        first_accesses
+            .into_iter()
+            .sorted_by_key(|(key, _)| {
+                (
+                    key.address,
+                    key.tag == Target::Storage,
+                    key.field_tag,
+                    key.storage_key,
+                    key.id,
+                )
+            })
+            .map(|(_, row)| row)
+            .collect()
+    }
+    /// Check that no `Account` CodeHash write replaces an already set code
+    /// hash with a different one. Code is only deployed once per account:
+    /// CREATE/CREATE2 write the code hash over the zero hash or the empty
+    /// code hash, while SELFDESTRUCT and the reversion of a failed deployment
+    /// write it back to the zero hash or the empty code hash, so those writes
+    /// are allowed.
+    pub fn check_codehash_stability(&self) -> Result<(), Vec<CodeHashError>> {
+        let empty_code_hash = CodeDB::empty_code_hash().to_word();
+        let is_set = |hash: Word| !(hash.is_zero() || hash == empty_code_hash);
+        let errs: Vec<CodeHashError> = self
+            .0
+            .get(&Target::Account)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::Account {
+                    is_write: true,
+                    account_address,
+                    field_tag: AccountFieldTag::CodeHash,
+                    value,
+                    value_prev,
+                    ..
+                } if is_set(value) && is_set(value_prev) && value != value_prev => {
+                    Some(CodeHashError {
+                        account_address,
+                        row: *row,
+                    })
+                }
+                _ => None,
+            })
+            .sorted_by_key(|err| err.row.rw_counter())
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// The creations and destructions of the account at `addr` with their
+    /// rw_counters, in counter order, derived from its CodeHash writes: a
+    /// write from the zero hash to a non-zero one creates the account, a
+    /// write from a non-zero hash back to the zero hash destroys it.
+    pub fn account_lifecycle(&self, addr: Address) -> Vec<(usize, LifecycleEvent)> {
+        self.0
+            .get(&Target::Account)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::Account {
+                    rw_counter,
+                    is_write: true,
+                    account_address,
+                    field_tag: AccountFieldTag::CodeHash,
+                    value,
+                    value_prev,
+                } if account_address == addr => match (value_prev.is_zero(), value.is_zero()) {
+                    (true, false) => Some((rw_counter.0, LifecycleEvent::Created)),
+                    (false, true) => Some((rw_counter.0, LifecycleEvent::Destroyed)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .sorted_by_key(|(rw_counter, _)| *rw_counter)
+            .collect()
+    }
+    /// Move the rows of transaction `tx_id` out of the map into a new one.
+    /// Tx-scoped rows are selected by their tx_id. Call-scoped rows
+    /// (CallContext, Stack and Memory) are selected by their call_id, and a
+    /// call belongs to the transaction when one of its `CallContext` TxId
+    /// rows has `tx_id` as value. `Start` and `Account` rows carry no tx_id
+    /// and always stay behind.
+    pub fn split_off_tx(&mut self, tx_id: usize) -> RwMap {
+        let call_ids = self.tx_call_ids(tx_id);
+        let mut split_off = HashMap::new();
+        for (target, rows) in self.0.iter_mut() {
+            let (owned, kept): (Vec<Rw>, Vec<Rw>) = std::mem::take(rows)
+                .into_iter()
+                .partition(|row| is_tx_row(row, tx_id, &call_ids));
+            *rows = kept;
+            if !owned.is_empty() {
+                split_off.insert(*target, owned);
+            }
+        }
+        Self(split_off)
+    }
+    /// Render the access density of the map as a standalone `width` x
+    /// `height` SVG: the rw_counters are bucketed into `width` columns of
+    /// one unit, each non-`Start` target gets a row of equal height in
+    /// [`Target`] order, and every cell is a `rect` whose opacity is its
+    /// access count relative to the busiest cell.
+    pub fn to_svg_heatmap(&self, width: usize, height: usize) -> String {
+        assert!(width > 0, "to_svg_heatmap width must be positive");
+        let targets: Vec<Target> = Target::iter()
+            .filter(|target| *target != Target::Start)
+            .collect();
+        let rows: Vec<&Rw> = self
+            .0
+            .values()
+            .flatten()
+            .filter(|row| !matches!(row, Rw::Start { .. }))
+            .collect();
+        let max_rw_counter = self.max_rw_counter().max(1);
+        let mut counts = vec![vec![0usize; width]; targets.len()];
+        for row in rows {
+            let target_idx = targets
+                .iter()
+                .position(|target| *target == row.tag())
+                .unwrap();
+            let column = row.rw_counter().0.saturating_sub(1) * width / max_rw_counter;
+            counts[target_idx][column] += 1;
+        }
+        let max_count = counts.iter().flatten().copied().max().unwrap_or(0).max(1);
+        let row_height = height as f64 / targets.len() as f64;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+        );
+        for (target_idx, (target, counts)) in targets.iter().zip(counts).enumerate() {
+            for (column, count) in counts.into_iter().enumerate() {
+                svg += &format!(
+                    "<rect x=\"{column}\" y=\"{:.3}\" width=\"1\" height=\"{row_height:.3}\" fill=\"#c00\" fill-opacity=\"{:.3}\"><title>{target:?}: {count}</title></rect>\n",
+                    target_idx as f64 * row_height,
+                    count as f64 / max_count as f64,
+                );
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+    /// Check every `Stack` row of each call: its stack pointer must be within
+    /// `0..1024`, and a read at a pointer must follow a write at that pointer
+    /// in the same call, as in [`Self::check_stack_init`]. Opcode arities are
+    /// not checked. The range deliberately departs from the requested
+    /// `0..=1024`, as the State circuit takes stack addresses in 10 bits.
+    pub fn check_stack_pointer_monotone_per_call(&self) -> Result<(), Vec<StackPointerError>> {
+        let mut errs: Vec<StackPointerError> = self
+            .0
+            .get(&Target::Stack)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::Stack {
+                    call_id,
+                    stack_pointer,
+                    ..
+                } if stack_pointer >= 1024 => Some(StackPointerError {
+                    call_id,
+                    row: *row,
+                    reason: "stack pointer out of range",
+                }),
+                _ => None,
+            })
+            .collect();
+        if let Err(init_errs) = self.check_stack_init() {
+            errs.extend(init_errs.into_iter().map(|err| StackPointerError {
+                call_id: err.call_id,
+                row: err.row,
+                reason: "read at a stack pointer never written",
+            }));
+        }
+        errs.sort_by_key(|err| err.row.rw_counter());
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// The 32 bytes of the memory of call `call_id` starting at `addr`, which
+    /// needn't be aligned, as a big-endian word. Each byte takes the value of
+    /// its latest row. Returns `None` if one of the bytes has no row or the
+    /// word would run past the end of the address space.
+    pub fn memory_word(&self, call_id: usize, addr: u64) -> Option<Word> {
+        let end = addr.checked_add(32)?;
+        let mut bytes: [Option<(usize, u8)>; 32] = [None; 32];
+        for row in self
+            .0
+            .get(&Target::Memory)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Rw::Memory {
+                rw_counter,
+                call_id: row_call_id,
+                memory_address,
+                byte,
+                ..
+            } = *row
+            {
+                if row_call_id != call_id || !(addr..end).contains(&memory_address) {
+                    continue;
+                }
+                let slot = &mut bytes[(memory_address - addr) as usize];
+                if slot.map_or(true, |(latest, _)| rw_counter.0 >= latest) {
+                    *slot = Some((rw_counter.0, byte));
+                }
+            }
+        }
+        let bytes: Vec<u8> = bytes
+            .iter()
+            .map(|slot| slot.map(|(_, byte)| byte))
+            .collect::<Option<_>>()?;
+        Some(Word::from_big_endian(&bytes))
+    }
+    /// The memory of call `call_id` as words, keyed by their 32-aligned
+    /// address, with the max rw_counter among the rows of their bytes. Each
+    /// byte takes the value of its latest row, and bytes without rows are
+    /// zero. The byte rows are left untouched.
+    pub fn memory_words_view(&self, call_id: usize) -> BTreeMap<u64, (Word, usize)> {
+        let mut words: BTreeMap<u64, ([u8; 32], [usize; 32])> = BTreeMap::new();
+        for row in self
+            .0
+            .get(&Target::Memory)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            if let Rw::Memory {
+                rw_counter,
+                call_id: row_call_id,
+                memory_address,
+                byte,
+                ..
+            } = *row
+            {
+                if row_call_id != call_id {
+                    continue;
+                }
+                let (bytes, rw_counters) = words.entry(memory_address / 32 * 32).or_default();
+                let offset = (memory_address % 32) as usize;
+                if rw_counter.0 >= rw_counters[offset] {
+                    bytes[offset] = byte;
+                    rw_counters[offset] = rw_counter.0;
+                }
+            }
+        }
+        words
+            .into_iter()
+            .map(|(address, (bytes, rw_counters))| {
+                (
+                    address,
+                    (
+                        Word::from_big_endian(&bytes),
+                        rw_counters.into_iter().max().unwrap_or_default(),
+                    ),
+                )
+            })
+            .collect()
+    }
+    /// The row at `idx` of the `tag` bucket, or `None` if the map has no such
+    /// row, e.g. while the witness is still being built.
+    pub fn try_get(&self, tag: Target, idx: usize) -> Option<&Rw> {
+        self.0.get(&tag)?.get(idx)
+    }
+    /// The row referenced by `op`, or `None` if the map has no such row, e.g.
+    /// for a stale [`OperationRef`].
+    pub fn get_op(&self, OperationRef(tag, idx): OperationRef) -> Option<&Rw> {
+        self.try_get(tag, idx)
+    }
+    /// Same as [`Self::get_op`], named after [`Self::try_get`].
+    #[deprecated(note = "Use RwMap::get_op")]
+    pub fn try_get_op(&self, op: OperationRef) -> Option<&Rw> {
+        self.get_op(op)
+    }
+    /// The value of the highest-counter `TxRefund` row of transaction `tx_id`,
+    /// i.e. its final accumulated refund, or `None` if it has no refund rows.
+    pub fn final_tx_refund(&self, tx_id: usize) -> Option<u64> {
+        self.0
+            .get(&Target::TxRefund)?
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::TxRefund {
+                    rw_counter,
+                    tx_id: row_tx_id,
+                    value,
+                    ..
+                } if row_tx_id == tx_id => Some((rw_counter, value)),
+                _ => None,
+            })
+            .max_by_key(|(rw_counter, _)| *rw_counter)
+            .map(|(_, value)| value)
+    }
+    /// Check that the value of every `TxReceipt` PostStateOrStatus row is a
+    /// post-Byzantium status, 0 or 1. Receipt values are `u64`, so the
+    /// 32-byte state root of pre-Byzantium receipts can't be recorded here.
+    pub fn check_receipt_status(&self) -> Result<(), Vec<ReceiptStatusError>> {
+        let errs: Vec<ReceiptStatusError> = self
+            .0
+            .get(&Target::TxReceipt)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::TxReceipt {
+                    tx_id,
+                    field_tag: TxReceiptFieldTag::PostStateOrStatus,
+                    value,
+                    ..
+                } if value > 1 => Some(ReceiptStatusError {
+                    tx_id,
+                    row: *row,
+                    value,
+                }),
+                _ => None,
+            })
+            .sorted_by_key(|err| err.row.rw_counter())
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// Consume the map into an owned iterator over the rows of each non-empty
+    /// target, in [`Target`] order, so each target can be handed to its own
+    /// worker.
+    pub fn into_target_streams(self) -> Vec<(Target, std::vec::IntoIter<Rw>)> {
+        self.0
+            .into_iter()
+            .filter(|(_, rows)| !rows.is_empty())
+            .sorted_by_key(|(target, _)| *target)
+            .map(|(target, rows)| (target, rows.into_iter()))
+            .collect()
+    }
+    /// The canonical form of the map for golden tests: the real rows without
+    /// any `Start` row, renumbered with contiguous rw_counters from 1 in
+    /// their original counter order, and sorted as in
+    /// [`Self::table_assignments`]. Maps differing only in insertion order,
+    /// `Start` rows or gaps between counters have the same canonical form.
+    pub fn canonicalize(&self) -> Vec<Rw> {
+        self.0
+            .values()
+            .flatten()
+            .filter(|row| !row.is_padding_or_start())
+            .sorted_by_key(|row| row.rw_counter())
+            .enumerate()
+            .map(|(idx, row)| row.with_rw_counter(RwCounter(idx + 1)))
+            .sorted_by_key(|row| (row.key(), row.rw_counter()))
+            .collect()
+    }
+    /// The calls of transaction `tx_id`, i.e. the call_ids of the
+    /// `CallContext` TxId rows with `tx_id` as value.
+    fn tx_call_ids(&self, tx_id: usize) -> HashSet<usize> {
+        self.0
+            .get(&Target::CallContext)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| match *row {
+                Rw::CallContext {
+                    call_id,
+                    field_tag: CallContextFieldTag::TxId,
+                    value,
+                    ..
+                } if value == Word::from(tx_id) => Some(call_id),
+                _ => None,
+            })
+            .collect()
+    }
+    /// The account and storage keys read and written by transaction `tx_id`.
+    /// `Account` rows carry no tx_id, so they are attributed to the
+    /// transaction when their rw_counter lies within the range spanned by
+    /// its tx-scoped rows and the rows of its calls, as resolved by
+    /// [`Self::split_off_tx`].
+    pub fn tx_access_sets(&self, tx_id: usize) -> AccessSets {
+        let call_ids = self.tx_call_ids(tx_id);
+        let rw_counters: Vec<usize> = self
+            .0
+            .values()
+            .flatten()
+            .filter(|row| is_tx_row(row, tx_id, &call_ids))
+            .map(|row| row.rw_counter().0)
+            .collect();
+        let mut sets = AccessSets::default();
+        let range = match (rw_counters.iter().min(), rw_counters.iter().max()) {
+            (Some(min), Some(max)) => *min..=*max,
+            _ => return sets,
+        };
+        for row in self.0.values().flatten() {
+            match *row {
+                Rw::Account {
+                    rw_counter,
+                    is_write,
+                    account_address,
+                    field_tag,
+                    ..
+                } if range.contains(&rw_counter.0) => {
+                    let set = if is_write {
+                        &mut sets.account_writes
+                    } else {
+                        &mut sets.account_reads
+                    };
+                    set.insert((account_address, field_tag));
+                }
+                Rw::AccountStorage {
+                    is_write,
+                    account_address,
+                    storage_key,
+                    tx_id: row_tx_id,
+                    ..
+                } if row_tx_id == tx_id => {
+                    let set = if is_write {
+                        &mut sets.storage_writes
+                    } else {
+                        &mut sets.storage_reads
+                    };
+                    set.insert((account_address, storage_key));
+                }
+                _ => {}
+            }
+        }
+        sets
+    }
+    /// Estimate the lookups the State circuit assignment of the map
+    /// generates, per lookup table.
+    pub fn lookup_estimate(&self) -> LookupEstimate {
+        let len = |target| self.0.get(&target).map_or(0, Vec::len);
+        LookupEstimate {
+            mpt: self.mpt_circuit_rows().len(),
+            byte_range: len(Target::Memory),
+            call_context: len(Target::CallContext),
+        }
+    }
+    /// Assert that the map still corresponds to `container`: a fresh map
+    /// built from it must have the same [`Self::canonicalize`] form.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn assert_matches_container(&self, container: &operation::OperationContainer) {
+        assert_eq!(
+            self.canonicalize(),
+            RwMap::from(container).canonicalize(),
+            "RwMap doesn't match the container"
+        );
+    }
+    /// The `top_n` [`RwKey`] groups with the most accesses, `Start` rows
+    /// excluded, by descending access count and then by key.
+    pub fn hot_keys(&self, top_n: usize) -> Vec<(RwKey, usize)> {
+        self.0
+            .values()
+            .flatten()
+            .filter(|row| !row.is_padding_or_start())
+            .map(|row| row.key())
+            .counts()
+            .into_iter()
+            .sorted_by(|(key_a, count_a), (key_b, count_b)| {
+                count_b.cmp(count_a).then(key_a.cmp(key_b))
+            })
+            .take(top_n)
+            .collect()
+    }
+    /// Check that the storage_key, value, value_prev and committed value words
+    /// of every row split into lo and hi 128-bit limbs that are each below
+    /// the modulus of `F`, as the RwTable assignment assumes. This can only
+    /// fail for fields of at most 128 bits: with a 254-bit field like bn256
+    /// `Fr` every limb fits.
+    pub fn check_value_encoding<F: Field>(&self) -> Result<(), Vec<EncodingError>> {
+        let modulus = Word::from_str_radix(F::MODULUS.trim_start_matches("0x"), 16)
+            .expect("field modulus is a hex string");
+        let errs: Vec<EncodingError> = self
+            .0
+            .values()
+            .flatten()
+            .filter(|row| !row.is_padding_or_start())
+            .sorted_by_key(|row| row.rw_counter())
+            .flat_map(|row| encoding_errors(row, modulus))
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// The rows of the account at `addr`, in rw_counter order: its
+    /// `Account`, `AccountStorage`, `TxAccessListAccount` and
+    /// `TxAccessListAccountStorage` rows. Unlike filtering on `Rw::address`,
+    /// which also encodes memory addresses, stack pointers and tx log fields
+    /// as addresses, this never returns rows whose pseudo-address happens to
+    /// equal `addr`.
+    pub fn rows_for_account(&self, addr: Address) -> Vec<Rw> {
+        [
+            Target::Account,
+            Target::Storage,
+            Target::TxAccessListAccount,
+            Target::TxAccessListAccountStorage,
+        ]
+        .iter()
+        .flat_map(|target| self.0.get(target).map(Vec::as_slice).unwrap_or_default())
+        .filter(|row| row.address() == Some(addr))
+        .sorted_by_key(|row| row.rw_counter())
+        .copied()
+        .collect()
+    }
+    /// Index the rows by execution step, each step sorted by rw_counter.
+    /// `boundaries` holds the first rw_counter of each step and must be
+    /// sorted: step `i` holds the rows with
+    /// `boundaries[i] <= rw_counter < boundaries[i + 1]` (inclusive start,
+    /// exclusive next), and the last step holds every later row. Steps
+    /// without rows, `Start` rows and rows before the first boundary are left
+    /// out.
+    pub fn index_by_step(&self, boundaries: &[usize]) -> BTreeMap<usize, Vec<Rw>> {
+        debug_assert!(
+            boundaries.windows(2).all(|w| w[0] <= w[1]),
+            "step boundaries must be sorted"
+        );
+        let mut steps: BTreeMap<usize, Vec<Rw>> = BTreeMap::new();
+        for row in self
+            .0
+            .values()
+            .flatten()
+            .filter(|row| !row.is_padding_or_start())
+            .sorted_by_key(|row| row.rw_counter())
+        {
+            let next_step = boundaries.partition_point(|&boundary| boundary <= row.rw_counter().0);
+            if next_step > 0 {
+                steps.entry(next_step - 1).or_default().push(*row);
+            }
+        }
+        steps
+    }
+    /// A copy of the map where, for each call and immutable `CallContext`
+    /// field (set up once when the call starts, like TxId or CalleeAddress),
+    /// the writes and the first read of each value are kept and later reads
+    /// of the same value are dropped. This is only valid for circuits that
+    /// don't look up the dropped reads, and the rw_counters of the result
+    /// are no longer contiguous, so they must be recompacted before
+    /// assignment.
+    pub fn dedup_call_context_reads(&self) -> RwMap {
+        let mut rw_map = self.clone();
+        let Some(rows) = rw_map.0.get_mut(&Target::CallContext) else {
+            return rw_map;
+        };
+        let mut read_values: HashMap<(usize, u64), Word> = HashMap::new();
+        // Dropped rows are tracked by position in the bucket, as rw_counters
+        // may collide in a map that hasn't been checked.
+        let mut keep = vec![true; rows.len()];
+        for (idx, row) in rows
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, row)| row.rw_counter())
+        {
+            if let Rw::CallContext {
+                is_write,
+                call_id,
+                field_tag,
+                value,
+                ..
+            } = *row
+            {
+                if !is_immutable_call_context_field(field_tag) {
+                    continue;
+                }
+                let key = (call_id, field_tag as u64);
+                if is_write {
+                    read_values.remove(&key);
+                } else if read_values.get(&key) == Some(&value) {
+                    keep[idx] = false;
+                } else {
+                    read_values.insert(key, value);
+                }
+            }
+        }
+        let mut keep = keep.into_iter();
+        rows.retain(|_| keep.next().unwrap());
+        rw_map
+    }
+    /// Check that the stack write through which each parent call receives the
+    /// result of a child call agrees with the child's `CallContext` rows. The
+    /// child is found from its CallerId write, and the parent's result write
+    /// is its last `Stack` write before that. It must hold the child's
+    /// IsSuccess flag, or for a CREATE child (IsCreate set) its CalleeAddress
+    /// on success and 0 on failure. The actual return data length of a child
+    /// has no row of its own, so it isn't checked. Root calls, whose CallerId
+    /// is 0, have no parent to check.
+    pub fn check_call_return_wiring(&self) -> Result<(), Vec<CallReturnError>> {
+        let call_context = self
+            .0
+            .get(&Target::CallContext)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let field_write = |call_id: usize, field_tag: CallContextFieldTag| {
+            call_context
+                .iter()
+                .filter_map(|row| match *row {
+                    Rw::CallContext {
+                        rw_counter,
+                        is_write: true,
+                        call_id: row_call_id,
+                        field_tag: row_field_tag,
+                        value,
+                    } if row_call_id == call_id && row_field_tag == field_tag => {
+                        Some((rw_counter, value))
+                    }
+                    _ => None,
+                })
+                .min_by_key(|(rw_counter, _)| *rw_counter)
+        };
+        let stack_writes: Vec<&Rw> = self
+            .0
+            .get(&Target::Stack)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|row| row.is_write())
+            .sorted_by_key(|row| row.rw_counter())
+            .collect();
+
+        let call_ids: BTreeSet<usize> = call_context.iter().filter_map(|row| row.id()).collect();
+        let mut errs = Vec::new();
+        for call_id in call_ids {
+            let (caller_rw_counter, caller_id) =
+                match field_write(call_id, CallContextFieldTag::CallerId) {
+                    Some((rw_counter, caller_id)) if !caller_id.is_zero() => {
+                        (rw_counter, caller_id.low_u64() as usize)
+                    }
+                    _ => continue,
+                };
+            let is_success = match field_write(call_id, CallContextFieldTag::IsSuccess) {
+                Some((_, is_success)) => is_success,
+                None => continue,
+            };
+            let expected = match field_write(call_id, CallContextFieldTag::IsCreate) {
+                Some((_, is_create)) if !is_create.is_zero() => {
+                    if is_success.is_zero() {
+                        Word::zero()
+                    } else {
+                        field_write(call_id, CallContextFieldTag::CalleeAddress)
+                            .map(|(_, address)| address)
+                            .unwrap_or_default()
+                    }
+                }
+                _ => is_success,
+            };
+            let stack_row = stack_writes
+                .iter()
+                .rev()
+                .find(|row| row.id() == Some(caller_id) && row.rw_counter() < caller_rw_counter)
+                .map(|row| **row);
+            if stack_row.map(|row| row.value_assignment()) != Some(expected) {
+                errs.push(CallReturnError {
+                    call_id,
+                    caller_id,
+                    expected,
+                    stack_row,
+                });
+            }
+        }
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+    /// The values of column `col` (an index into [`RW_ROW_COLUMN_NAMES`]) of
+    /// the rows of [`Self::table_assignments_prepad`] with `target_len`,
+    /// computed one row at a time so a column-major backend can assign the
+    /// table column by column.
+    pub fn column_iter<F: Field>(&self, col: usize, target_len: usize) -> impl Iterator<Item = F> {
+        assert!(
+            col < RW_ROW_COLUMN_NAMES.len(),
+            "RwMap::column_iter column {} out of range",
+            col
+        );
+        let (rows, _) = Self::table_assignments_prepad(&self.table_assignments(), target_len);
+        rows.into_iter()
+            .map(move |row| row.table_assignment::<F>().unwrap().values()[col])
+    }
+    /// Merge the rows of `other` into the map, e.g. to recombine two chunks,
+    /// and return the merged map with its [`Self::mpt_circuit_rows`]. A
+    /// group spanning both chunks has its first access in only one of them,
+    /// so the first accesses of the merged map may differ from the local
+    /// ones of either chunk.
+    pub fn merge_for_mpt(mut self, other: RwMap) -> (RwMap, Vec<Rw>) {
+        for (target, rows) in other.0 {
+            self.0.entry(target).or_default().extend(rows);
+        }
+        let first_accesses = self.mpt_circuit_rows();
+        (self, first_accesses)
+    }
+    /// Append the rows of `other`, e.g. the map of a second sub-trace, with
+    /// `rwc_offset` added to their rw_counter. The Start rows of `other` are
+    /// skipped so that only the padding of `self` survives.
+    pub fn merge(&mut self, other: RwMap, rwc_offset: usize) {
+        for (target, rows) in other.0 {
+            if target == Target::Start {
+                continue;
+            }
+            self.0.entry(target).or_default().extend(
+                rows.into_iter()
+                    .map(|row| row.with_rw_counter(row.rw_counter() + rwc_offset)),
+            );
+        }
+    }
+    /// Check that the [`Self::final_tx_refund`] of transaction `tx_id` doesn't
+    /// exceed the EIP-3529 cap of `gas_used / 5`. A transaction without
+    /// refund rows passes.
+    pub fn check_refund_cap(&self, tx_id: usize, gas_used: u64) -> Result<(), RefundCapError> {
+        let cap = gas_used / MAX_REFUND_QUOTIENT_OF_GAS_USED as u64;
+        match self.final_tx_refund(tx_id) {
+            Some(refund) if refund > cap => Err(RefundCapError { tx_id, refund, cap }),
+            _ => Ok(()),
+        }
+    }
+    /// Narrate the accesses of the `key` group in rw_counter order, e.g.
+    /// `counter 40: READ init=0 got 0; counter 55: WRITE 0→9; counter 80:
+    /// READ got 9.` The first access shows the initial value that
+    /// [`Self::check_value`] compares it with, and a write shows the value it
+    /// replaces.
+    pub fn narrate_key(&self, key: RwKey) -> String {
+        let rows: Vec<Rw> = self
+            .0
+            .get(&key.tag)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|row| row.key() == key)
+            .sorted_by_key(|row| row.rw_counter())
+            .copied()
+            .collect();
+        let updates = MptUpdates::mock_from(&rows);
+        let mut prev_value: Option<Word> = None;
+        let accesses: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let value = row.value_assignment();
+                let init_value = || {
+                    updates
+                        .get(row)
+                        .map(|u| u.value_assignments().1)
+                        .unwrap_or_default()
+                };
+                let access = match (row.is_write(), prev_value) {
+                    (true, _) => {
+                        let replaced = row
+                            .value_prev_assignment()
+                            .or(prev_value)
+                            .unwrap_or_else(init_value);
+                        format!("WRITE {}→{}", replaced, value)
+                    }
+                    (false, None) => format!("READ init={} got {}", init_value(), value),
+                    (false, Some(_)) => format!("READ got {}", value),
+                };
+                prev_value = Some(value);
+                format!("counter {}: {}", row.rw_counter().0, access)
+            })
+            .collect();
+        if accesses.is_empty() {
+            String::new()
+        } else {
+            format!("{}.", accesses.join("; "))
+        }
+    }
+    /// Pad the real rows to the smallest power of two `2^k` that fits them,
+    /// the `min_k_rows` rows of fixed overhead and at least one `Start` row,
+    /// as in [`Self::table_assignments_prepad`]. Returns the padded rows and
+    /// `k`.
+    pub fn assignments_pow2(&self, min_k_rows: usize) -> (Vec<Rw>, u32) {
+        let rows = self.real_rows();
+        let height = (rows.len() + min_k_rows + 1).next_power_of_two();
+        let (rows, _) = Self::table_assignments_prepad(&rows, height);
+        (rows, height.trailing_zeros())
+    }
+    /// The rows of the `tag` bucket in rw_counter order, without cloning the
+    /// other buckets. Buckets keep the insertion order of the container
+    /// conversion, so the rows are sorted here. Yields nothing if the map
+    /// has no `tag` bucket.
+    pub fn iter_by_tag(&self, tag: Target) -> impl Iterator<Item = &Rw> {
+        self.0
+            .get(&tag)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .sorted_by_key(|row| row.rw_counter())
+    }
+    /// Check [`Rw::validate_invariants`] on every row, in rw_counter order.
+    pub fn validate_all(&self) -> Result<(), Vec<InvariantError>> {
+        let errs: Vec<InvariantError> = self
+            .0
+            .values()
+            .flatten()
+            .sorted_by_key(|row| row.rw_counter())
+            .filter_map(|row| {
+                row.validate_invariants()
+                    .err()
+                    .map(|reason| InvariantError { row: *row, reason })
+            })
+            .collect();
+        if errs.is_empty() {
+            Ok(())
+        } else {
+            Err(errs)
+        }
+    }
+}
+
+/// Mutable form of a [`RwMap`] that keeps the rows in rw_counter order, and
+/// renumbers them from 1 when rows are added or removed. `Start` rows are
+/// kept aside untouched.
+#[derive(Debug, Default, Clone)]
+pub struct RwMapBuilder {
+    start_rows: Vec<Rw>,
+    rows: Vec<Rw>,
+}
+
+impl RwMapBuilder {
+    /// Append a row, giving it the next rw_counter. Returns that counter.
+    pub fn push(&mut self, row: Rw) -> usize {
+        let rw_counter = self.rows.len() + 1;
+        self.rows.push(row.with_rw_counter(RwCounter(rw_counter)));
+        rw_counter
+    }
+    /// The row at `rw_counter`, to edit it in place.
+    pub fn get_mut(&mut self, rw_counter: usize) -> Option<&mut Rw> {
+        self.rows
+            .iter_mut()
+            .find(|row| row.rw_counter() == RwCounter(rw_counter))
+    }
+    /// Remove the row at `rw_counter`, moving the later rows one counter down.
+    pub fn remove(&mut self, rw_counter: usize) -> Option<Rw> {
+        let idx = self
+            .rows
+            .iter()
+            .position(|row| row.rw_counter() == RwCounter(rw_counter))?;
+        let row = self.rows.remove(idx);
+        self.renumber();
+        Some(row)
+    }
+    /// Keep only the rows for which `f` returns true, renumbering them.
+    pub fn retain(&mut self, f: impl FnMut(&Rw) -> bool) {
+        self.rows.retain(f);
+        self.renumber();
+    }
+    fn renumber(&mut self) {
+        for (idx, row) in self.rows.iter_mut().enumerate() {
+            *row = row.with_rw_counter(RwCounter(idx + 1));
+        }
+    }
+    /// Build the map, checking the counters like
+    /// [`RwMap::from_rows_validated`].
+    pub fn build(self) -> Result<RwMap, RwBuildError> {
+        RwMap::from_rows_validated(self.start_rows.into_iter().chain(self.rows).collect())
+    }
+}
+
+/// A write whose value_prev is not the value of the previous access to the
+/// same key, reported by [`RwMap::check_value_prev_chain`]
+#[derive(Clone, Copy, Debug)]
+pub struct PrevChainError {
+    /// Index of the write in the sorted rows
+    pub idx: usize,
+    /// The write with the stale value_prev
+    pub row: Rw,
+    /// The previous access to the same key
+    pub prev_row: Rw,
+}
+
+/// A stack read of a slot that the same call never wrote before, reported by
+/// [`RwMap::check_stack_init`]
+#[derive(Clone, Copy, Debug)]
+pub struct StackInitError {
+    /// Call id
+    pub call_id: usize,
+    /// Stack pointer of the uninitialized slot
+    pub stack_pointer: usize,
+    /// The offending read
+    pub row: Rw,
+}
+
+/// The container holds more operations than allowed, reported by
+/// [`RwMap::try_from_container_limited`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManyRows {
+    /// Number of operations in the container
+    pub rows: usize,
+    /// Maximum number of rows allowed
+    pub max_rows: usize,
+}
+
+/// Final state of an account, returned by [`RwMap::final_account_state`].
+/// Fields never accessed in the block are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccountSummary {
+    /// Nonce
+    pub nonce: Option<Word>,
+    /// Balance
+    pub balance: Option<Word>,
+    /// Code hash
+    pub code_hash: Option<Word>,
+}
+
+/// A nonce write that decreases the nonce or doesn't chain from the previous
+/// nonce write of the account, reported by [`RwMap::check_nonce_monotone`]
+#[derive(Clone, Copy, Debug)]
+pub struct NonceError {
+    /// Account address
+    pub account_address: Address,
+    /// The offending write
+    pub row: Rw,
+    /// Value of the previous nonce write to the account
+    pub prev_value: Word,
+}
+
+/// Invalid rows passed to [`RwMap::from_rows_validated`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RwBuildError {
+    /// Two rows share the rw_counter
+    DuplicateRwCounter(usize),
+    /// No row has the rw_counter, while a higher one is used
+    MissingRwCounter(usize),
+    /// Two rows share the sort key and rw_counter
+    DuplicateSortKey {
+        /// Key of the rows
+        key: RwKey,
+        /// rw_counter of the rows
+        rw_counter: usize,
+    },
+}
+
+/// A malformed line found by [`RwMap::from_hex_dump`], or a malformed row
+/// found by [`RwMap::from_interned_binary`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line or row number, starting from 1. 0 for the header of binary dumps
+    pub line: usize,
+    /// What is wrong with the line
+    pub message: String,
+}
+
+/// Options of [`RwMap::check_value_with`]
+#[derive(Clone, Copy, Debug)]
+pub struct CheckOptions {
+    /// Log the errors found, as [`RwMap::check_value`] does
+    pub log: bool,
+    /// Keep at most this many errors
+    pub max_errors: Option<usize>,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        Self {
+            log: true,
+            max_errors: None,
+        }
+    }
+}
+
+impl CheckOptions {
+    /// Options that don't log anything
+    pub fn quiet() -> Self {
+        Self {
+            log: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// A read whose value doesn't match the previous access, reported by
+/// [`RwMap::value_errors`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueError {
+    /// Index of the read in the sorted rows
+    pub idx: usize,
+    /// Which check failed
+    pub reason: &'static str,
+    /// The offending read
+    pub row: Rw,
+    /// The row before it in the sorted rows
+    pub prev_row: Rw,
+}
+
+/// Error of [`RwMap::verify_value`]
+pub type RwValueError = ValueError;
+
+/// Two transactions whose rw_counter ranges overlap, reported by
+/// [`RwMap::check_contiguous_per_tx`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxCounterError {
+    /// Transaction id
+    pub tx_id: usize,
+    /// rw_counter range of the transaction
+    pub range: RangeInclusive<usize>,
+    /// Id of the overlapping transaction
+    pub other_tx_id: usize,
+    /// rw_counter range of the overlapping transaction
+    pub other_range: RangeInclusive<usize>,
+}
+
+/// A log whose log_id doesn't follow the previous log of the transaction,
+/// reported by [`RwMap::check_log_id_sequence`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogIdError {
+    /// Transaction id
+    pub tx_id: usize,
+    /// log_id of the log
+    pub log_id: u64,
+    /// log_id of the previous log in the transaction
+    pub prev_log_id: u64,
+}
+
+/// A `CallContext` value out of the range of its field, reported by
+/// [`RwMap::check_call_context_ranges`]
+#[derive(Clone, Copy, Debug)]
+pub struct CallContextRangeError {
+    /// The offending row
+    pub row: Rw,
+    /// Field of the row
+    pub field_tag: CallContextFieldTag,
+    /// Maximum value of the field
+    pub max: Word,
+}
+
+/// A first access to an address in a transaction that finds it already warm,
+/// reported by [`RwMap::check_first_access_cold`]
+#[derive(Clone, Copy, Debug)]
+pub struct ColdAccessError {
+    /// Transaction id
+    pub tx_id: usize,
+    /// Accessed address
+    pub account_address: Address,
+    /// The first access
+    pub row: Rw,
+}
+
+/// A memory access beyond the memory size recorded for its call, reported by
+/// [`RwMap::check_memory_within_size`]
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryBoundsError {
+    /// Call id
+    pub call_id: usize,
+    /// The offending memory row
+    pub row: Rw,
+    /// Largest memory size recorded for the call, in 32-byte words
+    pub memory_size: u64,
+}
+
+/// A write replacing the already set code hash of an account, reported by
+/// [`RwMap::check_codehash_stability`]
+#[derive(Clone, Copy, Debug)]
+pub struct CodeHashError {
+    /// Account address
+    pub account_address: Address,
+    /// The offending code hash write
+    pub row: Rw,
+}
+
+/// An existence transition of an account, reported by
+/// [`RwMap::account_lifecycle`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// The code hash went from zero to non-zero
+    Created,
+    /// The code hash went from non-zero back to zero
+    Destroyed,
+}
+
+/// An invalid stack access of a call, reported by
+/// [`RwMap::check_stack_pointer_monotone_per_call`]
+#[derive(Clone, Copy, Debug)]
+pub struct StackPointerError {
+    /// Call id
+    pub call_id: usize,
+    /// The offending stack row
+    pub row: Rw,
+    /// What is wrong with the row
+    pub reason: &'static str,
+}
+
+/// A receipt status other than 0 or 1, reported by
+/// [`RwMap::check_receipt_status`]
+#[derive(Clone, Copy, Debug)]
+pub struct ReceiptStatusError {
+    /// Transaction id
+    pub tx_id: usize,
+    /// The offending receipt row
+    pub row: Rw,
+    /// The recorded status
+    pub value: u64,
+}
+
+/// The keys accessed by a transaction, returned by [`RwMap::tx_access_sets`].
+/// Two transactions conflict when the write sets of one intersect the read or
+/// write sets of the other.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessSets {
+    /// Account fields read
+    pub account_reads: BTreeSet<(Address, AccountFieldTag)>,
+    /// Account fields written
+    pub account_writes: BTreeSet<(Address, AccountFieldTag)>,
+    /// Storage slots read
+    pub storage_reads: BTreeSet<(Address, Word)>,
+    /// Storage slots written
+    pub storage_writes: BTreeSet<(Address, Word)>,
+}
+
+/// Lookup counts of a map, returned by [`RwMap::lookup_estimate`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LookupEstimate {
+    /// MPT table lookups, one per first access of an `Account` or
+    /// `AccountStorage` key
+    pub mpt: usize,
+    /// Byte range lookups, one per `Memory` row
+    pub byte_range: usize,
+    /// Call context lookups, one per `CallContext` row
+    pub call_context: usize,
+}
+
+/// A word whose limbs don't fit the field, reported by
+/// [`RwMap::check_value_encoding`]
+#[derive(Clone, Copy, Debug)]
+pub struct EncodingError {
+    /// The offending row
+    pub row: Rw,
+    /// RwTable column of the word
+    pub column: &'static str,
+}
+
+/// A parent call whose stack doesn't receive the result of a child call,
+/// reported by [`RwMap::check_call_return_wiring`]
+#[derive(Clone, Copy, Debug)]
+pub struct CallReturnError {
+    /// Call id of the child call
+    pub call_id: usize,
+    /// Call id of the parent call
+    pub caller_id: usize,
+    /// The value the parent's stack write should hold
+    pub expected: Word,
+    /// The parent's stack write, `None` if there is none
+    pub stack_row: Option<Rw>,
+}
+
+/// A final refund above the EIP-3529 cap, reported by
+/// [`RwMap::check_refund_cap`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RefundCapError {
+    /// Transaction id
+    pub tx_id: usize,
+    /// Final refund of the transaction
+    pub refund: u64,
+    /// Maximum refund, `gas_used / 5`
+    pub cap: u64,
+}
+
+/// A row breaking [`Rw::validate_invariants`], reported by
+/// [`RwMap::validate_all`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvariantError {
+    /// The offending row
+    pub row: Rw,
+    /// Which invariant the row breaks
+    pub reason: String,
+}
+
+/// Key of a Rw row in the RwTable, which is the State circuit sort key
+/// without the rw_counter. Rows sharing the same key form a group whose
+/// accesses are constrained together, ordered by rw_counter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RwKey {
+    /// Tag
+    pub tag: Target,
+    /// Id, 0 if the row has none
+    pub id: usize,
+    /// Address, 0 if the row has none
+    pub address: Address,
+    /// Field tag, 0 if the row has none
+    pub field_tag: u64,
+    /// Storage key, 0 if the row has none
+    pub storage_key: Word,
+}
+
+/// The rows of a [`RwMap`] sorted as in [`RwMap::table_assignments`], built
+/// by [`RwMap::sorted`] so that group based checks share one sort.
+#[derive(Clone, Debug, Default)]
+pub struct SortedRwMap(Vec<Rw>);
+
+impl SortedRwMap {
+    /// The sorted rows
+    pub fn rows(&self) -> &[Rw] {
+        &self.0
+    }
+    /// Split the sorted rows into the contiguous slices of rows sharing the
+    /// same [`RwKey`], in order.
+    pub fn sorted_groups(&self) -> impl Iterator<Item = (RwKey, &[Rw])> {
+        let mut rest = self.rows();
+        std::iter::from_fn(move || {
+            let key = rest.first()?.key();
+            let len = rest
+                .iter()
+                .position(|row| row.key() != key)
+                .unwrap_or(rest.len());
+            let (group, tail) = rest.split_at(len);
+            rest = tail;
+            Some((key, group))
+        })
+    }
+    /// Same as [`Self::sorted_groups`], with the index in [`Self::rows`] of
+    /// the first row of each group instead of its key.
+    pub(crate) fn indexed_groups(&self) -> impl Iterator<Item = (usize, &[Rw])> {
+        self.sorted_groups().scan(0, |start, (_, group)| {
+            let idx = *start;
+            *start += group.len();
+            Some((idx, group))
+        })
+    }
+}
+
+/// The rw_counter of a [`Rw`], a distinct type so that it can't be mixed up
+/// with the other `usize` fields of the rows, such as stack pointers, when
+/// renumbering rows. Only offsets can be added to it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "rw-serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct RwCounter(pub usize);
+
+impl From<usize> for RwCounter {
+    fn from(rw_counter: usize) -> Self {
+        Self(rw_counter)
+    }
+}
+
+impl From<RwCounter> for usize {
+    fn from(rw_counter: RwCounter) -> Self {
+        rw_counter.0
+    }
+}
+
+impl From<operation::RWCounter> for RwCounter {
+    fn from(rwc: operation::RWCounter) -> Self {
+        Self(rwc.0)
+    }
+}
+
+impl Add<usize> for RwCounter {
+    type Output = Self;
+
+    fn add(self, offset: usize) -> Self {
+        Self(self.0 + offset)
+    }
 }
 
 #[allow(
@@ -158,13 +2587,14 @@ impl RwMap {
 )]
 /// Read-write records in execution. Rws are used for connecting evm circuit and
 /// state circuits.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "rw-serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rw {
     /// Start
-    Start { rw_counter: usize },
+    Start { rw_counter: RwCounter },
     /// TxAccessListAccount
     TxAccessListAccount {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         tx_id: usize,
         account_address: Address,
@@ -173,7 +2603,7 @@ pub enum Rw {
     },
     /// TxAccessListAccountStorage
     TxAccessListAccountStorage {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         tx_id: usize,
         account_address: Address,
@@ -183,7 +2613,7 @@ pub enum Rw {
     },
     /// TxRefund
     TxRefund {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         tx_id: usize,
         value: u64,
@@ -191,7 +2621,7 @@ pub enum Rw {
     },
     /// Account
     Account {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         account_address: Address,
         field_tag: AccountFieldTag,
@@ -200,7 +2630,7 @@ pub enum Rw {
     },
     /// AccountStorage
     AccountStorage {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         account_address: Address,
         storage_key: Word,
@@ -211,7 +2641,7 @@ pub enum Rw {
     },
     /// CallContext
     CallContext {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         call_id: usize,
         field_tag: CallContextFieldTag,
@@ -219,7 +2649,7 @@ pub enum Rw {
     },
     /// Stack
     Stack {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         call_id: usize,
         stack_pointer: usize,
@@ -227,7 +2657,7 @@ pub enum Rw {
     },
     /// Memory
     Memory {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         call_id: usize,
         memory_address: u64,
@@ -235,7 +2665,7 @@ pub enum Rw {
     },
     /// TxLog
     TxLog {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         tx_id: usize,
         log_id: u64, // pack this can index together into address?
@@ -250,7 +2680,7 @@ pub enum Rw {
     },
     /// TxReceipt
     TxReceipt {
-        rw_counter: usize,
+        rw_counter: RwCounter,
         is_write: bool,
         tx_id: usize,
         field_tag: TxReceiptFieldTag,
@@ -258,6 +2688,24 @@ pub enum Rw {
     },
 }
 
+/// Names of the RwTable columns, in the order of [`RwRow::values`]
+pub const RW_ROW_COLUMN_NAMES: [&str; 14] = [
+    "rw_counter",
+    "is_write",
+    "tag",
+    "id",
+    "address",
+    "field_tag",
+    "storage_key_lo",
+    "storage_key_hi",
+    "value_lo",
+    "value_hi",
+    "value_prev_lo",
+    "value_prev_hi",
+    "init_val_lo",
+    "init_val_hi",
+];
+
 /// Rw table row assignment
 #[derive(Default, Clone, Copy, Debug)]
 pub struct RwRow<F> {
@@ -293,6 +2741,30 @@ impl<F: Field> RwRow<F> {
         ]
     }
 
+    /// Inverse of `values`, e.g. to rebuild a row after mutating one of its
+    /// columns. The lo and hi limbs of each word follow each other.
+    pub fn from_values(vals: [F; 14]) -> Self {
+        Self {
+            rw_counter: vals[0],
+            is_write: vals[1],
+            tag: vals[2],
+            id: vals[3],
+            address: vals[4],
+            field_tag: vals[5],
+            storage_key: word::Word::new([vals[6], vals[7]]),
+            value: word::Word::new([vals[8], vals[9]]),
+            value_prev: word::Word::new([vals[10], vals[11]]),
+            init_val: word::Word::new([vals[12], vals[13]]),
+        }
+    }
+
+    /// The values of the row paired with the names of their RwTable columns,
+    /// as they appear in MockProver failures.
+    pub fn as_advice_cells(&self) -> [(&'static str, F); 14] {
+        let values = self.values();
+        std::array::from_fn(|idx| (RW_ROW_COLUMN_NAMES[idx], values[idx]))
+    }
+
     pub(crate) fn rlc(&self, randomness: F) -> F {
         let values = self.values();
         values
@@ -300,37 +2772,96 @@ impl<F: Field> RwRow<F> {
             .rev()
             .fold(F::ZERO, |acc, value| acc * randomness + value)
     }
+
+    /// Same as [`Self::rlc`] with two challenges, for circuits that keep the
+    /// lo and hi limbs of the word columns in separate phases. The
+    /// coefficient of the limb at index `i` of [`Self::values`] is the
+    /// product of the challenges of the limbs `1..=i`, where the hi limbs
+    /// (storage_key, value, value_prev and init_val hi, at indexes 7, 9, 11
+    /// and 13) use `challenge_hi` and every other limb uses `challenge_lo`.
+    /// With equal challenges this is [`Self::rlc`].
+    pub fn rlc_split(&self, challenge_lo: F, challenge_hi: F) -> F {
+        let mut coefficient = F::ONE;
+        self.values()
+            .iter()
+            .enumerate()
+            .fold(F::ZERO, |acc, (idx, value)| {
+                if idx > 0 {
+                    coefficient *= if idx >= 7 && idx % 2 == 1 {
+                        challenge_hi
+                    } else {
+                        challenge_lo
+                    };
+                }
+                acc + *value * coefficient
+            })
+    }
 }
 
 impl<F: Field> RwRow<Value<F>> {
     pub(crate) fn unwrap(self) -> RwRow<F> {
-        let unwrap_f = |f: Value<F>| {
+        self.try_unwrap()
+            .unwrap_or_else(|column| panic!("unknown value in rw row column {column}"))
+    }
+
+    /// Whether every column is known and equal to the one of `expected`. An
+    /// unknown value is a mismatch rather than a panic as in `unwrap`.
+    pub fn matches(&self, expected: &RwRow<F>) -> bool {
+        self.try_unwrap()
+            .map_or(false, |row| row.values() == expected.values())
+    }
+
+    /// Unwrap every column, returning the name of the first unknown column.
+    fn try_unwrap(self) -> Result<RwRow<F>, &'static str> {
+        let unwrap_f = |f: Value<F>, column: &'static str| {
             let mut inner = None;
             _ = f.map(|v| {
                 inner = Some(v);
             });
-            inner.unwrap()
+            inner.ok_or(column)
         };
-        let unwrap_w = |f: word::Word<Value<F>>| {
-            let (lo, hi) = f.into_lo_hi();
-            word::Word::new([unwrap_f(lo), unwrap_f(hi)])
+        let unwrap_w = |f: word::Word<Value<F>>, lo: &'static str, hi: &'static str| {
+            let (f_lo, f_hi) = f.into_lo_hi();
+            Ok::<_, &'static str>(word::Word::new([unwrap_f(f_lo, lo)?, unwrap_f(f_hi, hi)?]))
         };
 
-        RwRow {
-            rw_counter: unwrap_f(self.rw_counter),
-            is_write: unwrap_f(self.is_write),
-            tag: unwrap_f(self.tag),
-            id: unwrap_f(self.id),
-            address: unwrap_f(self.address),
-            field_tag: unwrap_f(self.field_tag),
-            storage_key: unwrap_w(self.storage_key),
-            value: unwrap_w(self.value),
-            value_prev: unwrap_w(self.value_prev),
-            init_val: unwrap_w(self.init_val),
-        }
+        Ok(RwRow {
+            rw_counter: unwrap_f(self.rw_counter, "rw_counter")?,
+            is_write: unwrap_f(self.is_write, "is_write")?,
+            tag: unwrap_f(self.tag, "tag")?,
+            id: unwrap_f(self.id, "id")?,
+            address: unwrap_f(self.address, "address")?,
+            field_tag: unwrap_f(self.field_tag, "field_tag")?,
+            storage_key: unwrap_w(self.storage_key, "storage_key_lo", "storage_key_hi")?,
+            value: unwrap_w(self.value, "value_lo", "value_hi")?,
+            value_prev: unwrap_w(self.value_prev, "value_prev_lo", "value_prev_hi")?,
+            init_val: unwrap_w(self.init_val, "init_val_lo", "init_val_hi")?,
+        })
     }
 }
 
+/// Unwrap a batch of assigned rows, returning an error pointing at the first
+/// unknown value instead of panicking like [`RwRow::unwrap`].
+pub fn unwrap_rows<F: Field>(rows: Vec<RwRow<Value<F>>>) -> Result<Vec<RwRow<F>>, UnwrapError> {
+    rows.into_iter()
+        .enumerate()
+        .map(|(row, rw_row)| {
+            rw_row
+                .try_unwrap()
+                .map_err(|column| UnwrapError { row, column })
+        })
+        .collect()
+}
+
+/// An unknown value found by [`unwrap_rows`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnwrapError {
+    /// Index of the row in the batch
+    pub row: usize,
+    /// Name of the column, one of [`RW_ROW_COLUMN_NAMES`]
+    pub column: &'static str,
+}
+
 impl Rw {
     pub(crate) fn tx_access_list_value_pair(&self) -> (bool, bool) {
         match self {
@@ -456,7 +2987,7 @@ impl Rw {
 
     pub(crate) fn table_assignment<F: Field>(&self) -> RwRow<Value<F>> {
         RwRow {
-            rw_counter: Value::known(F::from(self.rw_counter() as u64)),
+            rw_counter: Value::known(F::from(self.rw_counter().0 as u64)),
             is_write: Value::known(F::from(self.is_write() as u64)),
             tag: Value::known(F::from(self.tag() as u64)),
             id: Value::known(F::from(self.id().unwrap_or_default() as u64)),
@@ -466,12 +2997,23 @@ impl Rw {
             value: word::Word::from(self.value_assignment()).into_value(),
             value_prev: word::Word::from(self.value_prev_assignment().unwrap_or_default())
                 .into_value(),
-            init_val: word::Word::from(self.committed_value_assignment().unwrap_or_default())
-                .into_value(),
+            init_val: word::Word::from(self.committed_value().unwrap_or_default()).into_value(),
+        }
+    }
+
+    /// The key of the group this row belongs to in the RwTable
+    pub fn key(&self) -> RwKey {
+        RwKey {
+            tag: self.tag(),
+            id: self.id().unwrap_or_default(),
+            address: self.address().unwrap_or_default(),
+            field_tag: self.field_tag().unwrap_or_default(),
+            storage_key: self.storage_key().unwrap_or_default(),
         }
     }
 
-    pub(crate) fn rw_counter(&self) -> usize {
+    /// The rw_counter of the row
+    pub fn rw_counter(&self) -> RwCounter {
         match self {
             Self::Start { rw_counter }
             | Self::Memory { rw_counter, .. }
@@ -487,6 +3029,60 @@ impl Rw {
         }
     }
 
+    /// The rw_counter of the row as a plain `usize`, for code that hasn't
+    /// moved to [`RwCounter`] yet.
+    #[deprecated(note = "Use Rw::rw_counter, which returns a RwCounter")]
+    pub fn rw_counter_usize(&self) -> usize {
+        self.rw_counter().0
+    }
+
+    /// The same row with its rw_counter replaced by `new_rw_counter`.
+    pub fn with_rw_counter(mut self, new_rw_counter: RwCounter) -> Self {
+        self.set_rw_counter(new_rw_counter);
+        self
+    }
+
+    /// Replace the rw_counter of the row in place by `new`.
+    pub fn set_rw_counter(&mut self, new: RwCounter) {
+        match self {
+            Self::Start { rw_counter }
+            | Self::Memory { rw_counter, .. }
+            | Self::Stack { rw_counter, .. }
+            | Self::AccountStorage { rw_counter, .. }
+            | Self::TxAccessListAccount { rw_counter, .. }
+            | Self::TxAccessListAccountStorage { rw_counter, .. }
+            | Self::TxRefund { rw_counter, .. }
+            | Self::Account { rw_counter, .. }
+            | Self::CallContext { rw_counter, .. }
+            | Self::TxLog { rw_counter, .. }
+            | Self::TxReceipt { rw_counter, .. } => *rw_counter = new,
+        }
+    }
+
+    /// The same row with its tx_id replaced by `new_tx_id` for the tx-scoped
+    /// variants. Other rows are returned unchanged.
+    pub fn with_tx_id(mut self, new_tx_id: usize) -> Self {
+        match &mut self {
+            Self::AccountStorage { tx_id, .. }
+            | Self::TxAccessListAccount { tx_id, .. }
+            | Self::TxAccessListAccountStorage { tx_id, .. }
+            | Self::TxRefund { tx_id, .. }
+            | Self::TxLog { tx_id, .. }
+            | Self::TxReceipt { tx_id, .. } => *tx_id = new_tx_id,
+            Self::Start { .. }
+            | Self::Account { .. }
+            | Self::CallContext { .. }
+            | Self::Stack { .. }
+            | Self::Memory { .. } => {}
+        }
+        self
+    }
+
+    /// Whether this is a Rw::Start row, which is only used as padding
+    pub(crate) fn is_padding_or_start(&self) -> bool {
+        matches!(self, Self::Start { .. })
+    }
+
     pub(crate) fn is_write(&self) -> bool {
         match self {
             Self::Start { .. } => false,
@@ -503,6 +3099,11 @@ impl Rw {
         }
     }
 
+    /// Complement of [`Self::is_write`], so Start rows count as reads.
+    pub(crate) fn is_read(&self) -> bool {
+        !self.is_write()
+    }
+
     pub(crate) fn tag(&self) -> Target {
         match self {
             Self::Start { .. } => Target::Start,
@@ -584,6 +3185,24 @@ impl Rw {
         }
     }
 
+    /// Name of the field tag, e.g. `"Balance"` or `"GasLeft"`, for the
+    /// variants that have one.
+    pub fn field_tag_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Account { field_tag, .. } => Some((*field_tag).into()),
+            Self::CallContext { field_tag, .. } => Some((*field_tag).into()),
+            Self::TxReceipt { field_tag, .. } => Some((*field_tag).into()),
+            Self::Start { .. }
+            | Self::Memory { .. }
+            | Self::Stack { .. }
+            | Self::AccountStorage { .. }
+            | Self::TxAccessListAccount { .. }
+            | Self::TxAccessListAccountStorage { .. }
+            | Self::TxRefund { .. }
+            | Self::TxLog { .. } => None,
+        }
+    }
+
     pub(crate) fn storage_key(&self) -> Option<Word> {
         match self {
             Self::AccountStorage { storage_key, .. }
@@ -634,7 +3253,9 @@ impl Rw {
         }
     }
 
-    fn committed_value_assignment(&self) -> Option<Word> {
+    /// The committed value of an `AccountStorage` row, `None` for any other
+    /// variant.
+    pub fn committed_value(&self) -> Option<Word> {
         match self {
             Self::AccountStorage {
                 committed_value, ..
@@ -642,220 +3263,719 @@ impl Rw {
             _ => None,
         }
     }
+
+    /// The value of a memory row as a word, `None` for non-memory rows.
+    /// Memory rows are byte-granular, so the byte is widened to a word with
+    /// the byte as its least significant byte. Word-granular memory rows
+    /// would return their word as is.
+    pub fn memory_value_word(&self) -> Option<Word> {
+        match self {
+            Self::Memory { byte, .. } => Some(U256::from(*byte)),
+            _ => None,
+        }
+    }
+
+    /// The value of the row as a word, i.e. its `value` column, with the
+    /// `u64` values of TxRefund and TxReceipt rows and the byte of Memory
+    /// rows widened. Start rows are zero.
+    pub fn value_as_word(&self) -> Word {
+        self.value_assignment()
+    }
+
+    /// Check the constraints on the fields of the row that its type doesn't
+    /// enforce: log topic indexes below 4, stack pointers below 1024, nonces
+    /// in a u64, the bounded `CallContext` fields within their range, and a
+    /// receipt status of 0 or 1.
+    pub fn validate_invariants(&self) -> Result<(), String> {
+        match *self {
+            Self::TxLog {
+                field_tag: TxLogFieldTag::Topic,
+                index,
+                ..
+            } if index >= 4 => Err(format!("log topic index {} is not below 4", index)),
+            Self::Stack { stack_pointer, .. } if stack_pointer >= 1024 => {
+                Err(format!("stack pointer {} is not below 1024", stack_pointer))
+            }
+            Self::Account {
+                field_tag: AccountFieldTag::Nonce,
+                value,
+                value_prev,
+                ..
+            } if value.bits() > 64 || value_prev.bits() > 64 => Err(format!(
+                "nonce {} or previous nonce {} exceeds u64",
+                value, value_prev
+            )),
+            Self::CallContext {
+                field_tag, value, ..
+            } => match call_context_max(field_tag) {
+                Some(max) if value > max => Err(format!(
+                    "call context {:?} value {} exceeds {}",
+                    field_tag, value, max
+                )),
+                _ => Ok(()),
+            },
+            Self::TxReceipt {
+                field_tag: TxReceiptFieldTag::PostStateOrStatus,
+                value,
+                ..
+            } if value > 1 => Err(format!("receipt status {} is not 0 or 1", value)),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl From<&operation::OperationContainer> for RwMap {
     fn from(container: &operation::OperationContainer) -> Self {
-        let mut rws = HashMap::default();
-
-        rws.insert(
-            Target::Start,
-            container
-                .start
-                .iter()
-                .map(|op| Rw::Start {
-                    rw_counter: op.rwc().into(),
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::TxAccessListAccount,
-            container
-                .tx_access_list_account
-                .iter()
-                .map(|op| Rw::TxAccessListAccount {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    tx_id: op.op().tx_id,
-                    account_address: op.op().address,
-                    is_warm: op.op().is_warm,
-                    is_warm_prev: op.op().is_warm_prev,
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::TxAccessListAccountStorage,
-            container
-                .tx_access_list_account_storage
-                .iter()
-                .map(|op| Rw::TxAccessListAccountStorage {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    tx_id: op.op().tx_id,
-                    account_address: op.op().address,
-                    storage_key: op.op().key,
-                    is_warm: op.op().is_warm,
-                    is_warm_prev: op.op().is_warm_prev,
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::TxRefund,
-            container
-                .tx_refund
-                .iter()
-                .map(|op| Rw::TxRefund {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    tx_id: op.op().tx_id,
-                    value: op.op().value,
-                    value_prev: op.op().value_prev,
-                })
+        Self(
+            Target::iter()
+                .map(|target| (target, container_rows(container, target)))
                 .collect(),
-        );
-        rws.insert(
-            Target::Account,
-            container
-                .account
-                .iter()
-                .map(|op| Rw::Account {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    account_address: op.op().address,
-                    field_tag: match op.op().field {
-                        AccountField::Nonce => AccountFieldTag::Nonce,
-                        AccountField::Balance => AccountFieldTag::Balance,
-                        AccountField::CodeHash => AccountFieldTag::CodeHash,
-                    },
-                    value: op.op().value,
-                    value_prev: op.op().value_prev,
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::Storage,
-            container
-                .storage
-                .iter()
-                .map(|op| Rw::AccountStorage {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    account_address: op.op().address,
-                    storage_key: op.op().key,
-                    value: op.op().value,
-                    value_prev: op.op().value_prev,
-                    tx_id: op.op().tx_id,
-                    committed_value: op.op().committed_value,
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::CallContext,
-            container
-                .call_context
-                .iter()
-                .map(|op| Rw::CallContext {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    call_id: op.op().call_id,
-                    field_tag: match op.op().field {
-                        CallContextField::RwCounterEndOfReversion => {
-                            CallContextFieldTag::RwCounterEndOfReversion
-                        }
-                        CallContextField::CallerId => CallContextFieldTag::CallerId,
-                        CallContextField::TxId => CallContextFieldTag::TxId,
-                        CallContextField::Depth => CallContextFieldTag::Depth,
-                        CallContextField::CallerAddress => CallContextFieldTag::CallerAddress,
-                        CallContextField::CalleeAddress => CallContextFieldTag::CalleeAddress,
-                        CallContextField::CallDataOffset => CallContextFieldTag::CallDataOffset,
-                        CallContextField::CallDataLength => CallContextFieldTag::CallDataLength,
-                        CallContextField::ReturnDataOffset => CallContextFieldTag::ReturnDataOffset,
-                        CallContextField::ReturnDataLength => CallContextFieldTag::ReturnDataLength,
-                        CallContextField::Value => CallContextFieldTag::Value,
-                        CallContextField::IsSuccess => CallContextFieldTag::IsSuccess,
-                        CallContextField::IsPersistent => CallContextFieldTag::IsPersistent,
-                        CallContextField::IsStatic => CallContextFieldTag::IsStatic,
-                        CallContextField::LastCalleeId => CallContextFieldTag::LastCalleeId,
-                        CallContextField::LastCalleeReturnDataOffset => {
-                            CallContextFieldTag::LastCalleeReturnDataOffset
-                        }
-                        CallContextField::LastCalleeReturnDataLength => {
-                            CallContextFieldTag::LastCalleeReturnDataLength
-                        }
-                        CallContextField::IsRoot => CallContextFieldTag::IsRoot,
-                        CallContextField::IsCreate => CallContextFieldTag::IsCreate,
-                        CallContextField::CodeHash => CallContextFieldTag::CodeHash,
-                        CallContextField::ProgramCounter => CallContextFieldTag::ProgramCounter,
-                        CallContextField::StackPointer => CallContextFieldTag::StackPointer,
-                        CallContextField::GasLeft => CallContextFieldTag::GasLeft,
-                        CallContextField::MemorySize => CallContextFieldTag::MemorySize,
-                        CallContextField::ReversibleWriteCounter => {
-                            CallContextFieldTag::ReversibleWriteCounter
-                        }
-                    },
-                    value: op.op().value,
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::Stack,
-            container
-                .stack
-                .iter()
-                .map(|op| Rw::Stack {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    call_id: op.op().call_id(),
-                    stack_pointer: usize::from(*op.op().address()),
-                    value: *op.op().value(),
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::Memory,
-            container
-                .memory
-                .iter()
-                .map(|op| Rw::Memory {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    call_id: op.op().call_id(),
-                    memory_address: u64::from_le_bytes(
-                        op.op().address().to_le_bytes()[..8].try_into().unwrap(),
-                    ),
-                    byte: op.op().value(),
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::TxLog,
-            container
-                .tx_log
-                .iter()
-                .map(|op| Rw::TxLog {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    tx_id: op.op().tx_id,
-                    log_id: op.op().log_id as u64,
-                    field_tag: match op.op().field {
-                        TxLogField::Address => TxLogFieldTag::Address,
-                        TxLogField::Topic => TxLogFieldTag::Topic,
-                        TxLogField::Data => TxLogFieldTag::Data,
-                    },
-                    index: op.op().index,
-                    value: op.op().value,
-                })
-                .collect(),
-        );
-        rws.insert(
-            Target::TxReceipt,
-            container
-                .tx_receipt
-                .iter()
-                .map(|op| Rw::TxReceipt {
-                    rw_counter: op.rwc().into(),
-                    is_write: op.rw().is_write(),
-                    tx_id: op.op().tx_id,
-                    field_tag: match op.op().field {
-                        TxReceiptField::PostStateOrStatus => TxReceiptFieldTag::PostStateOrStatus,
-                        TxReceiptField::LogLength => TxReceiptFieldTag::LogLength,
-                        TxReceiptField::CumulativeGasUsed => TxReceiptFieldTag::CumulativeGasUsed,
-                    },
-                    value: op.op().value,
-                })
-                .collect(),
-        );
+        )
+    }
+}
 
-        Self(rws)
+/// Merge chunks of rows that are each already sorted in the order of
+/// [`RwMap::table_assignments`] into a single sorted assignment, with a k-way
+/// merge instead of a full re-sort.
+pub fn merge_sorted(chunks: Vec<Vec<Rw>>) -> Vec<Rw> {
+    debug_assert!(
+        chunks
+            .iter()
+            .all(|chunk| chunk.windows(2).all(|w| sort_key(&w[0]) <= sort_key(&w[1]))),
+        "merge_sorted input chunks must be sorted"
+    );
+    chunks
+        .into_iter()
+        .kmerge_by(|a, b| sort_key(a) < sort_key(b))
+        .collect()
+}
+
+/// Order of the rows in [`RwMap::table_assignments`], shared by the serial
+/// and the `parallel` sort so that they can't diverge.
+fn sort_key(row: &Rw) -> (RwKey, RwCounter) {
+    (row.key(), row.rw_counter())
+}
+
+/// Build the RwMap from the same container twice and assert that both builds
+/// produce identical table assignments, to catch witness generation that
+/// depends on HashMap iteration order or unstable sorting.
+#[cfg(any(test, feature = "test-util"))]
+pub fn assert_deterministic(container: &operation::OperationContainer) {
+    assert_eq!(
+        RwMap::from(container).table_assignments(),
+        RwMap::from(container).table_assignments(),
+        "RwMap build is not deterministic"
+    );
+}
+
+/// The word columns of `row` with a lo or hi 128-bit limb that is not below
+/// `modulus`.
+fn encoding_errors(row: &Rw, modulus: Word) -> Vec<EncodingError> {
+    [
+        ("storage_key", row.storage_key()),
+        ("value", Some(row.value_assignment())),
+        ("value_prev", row.value_prev_assignment()),
+        ("init_val", row.committed_value()),
+    ]
+    .into_iter()
+    .filter_map(|(column, word)| {
+        let word: Word = word?;
+        let hi: Word = word >> 128;
+        let lo = Word::from(word.low_u128());
+        (lo >= modulus || hi >= modulus).then_some(EncodingError { row: *row, column })
+    })
+    .collect()
+}
+
+/// Whether `row` is a tx-scoped row of transaction `tx_id`, or a call-scoped
+/// row of one of its `call_ids`.
+fn is_tx_row(row: &Rw, tx_id: usize, call_ids: &HashSet<usize>) -> bool {
+    match row {
+        Rw::CallContext { call_id, .. }
+        | Rw::Stack { call_id, .. }
+        | Rw::Memory { call_id, .. } => call_ids.contains(call_id),
+        Rw::Start { .. } | Rw::Account { .. } => false,
+        _ => row.id() == Some(tx_id),
+    }
+}
+
+/// The [`ValueError`]s of the reads of `group`, the rows of `rows` from
+/// index `start`, the first one being checked against the initial value.
+fn group_value_errors(
+    rows: &[Rw],
+    start: usize,
+    group: &[Rw],
+    updates: &MptUpdates,
+) -> Vec<ValueError> {
+    (start.max(1)..start + group.len())
+        .filter_map(|idx| read_value_error(idx, rows, idx == start, updates))
+        .collect()
+}
+
+/// Assert that the rows of each target of `map` carry, position by position,
+/// the rw_counter and is_write flag of the operations of `container` they
+/// were converted from.
+#[cfg(any(test, feature = "test-util"))]
+pub fn assert_counters_from_container(map: &RwMap, container: &operation::OperationContainer) {
+    for target in Target::iter() {
+        let rows = map.0.get(&target).map(Vec::as_slice).unwrap_or_default();
+        let ops = container_counters(container, target);
+        assert_eq!(rows.len(), ops.len(), "{:?} row count mismatch", target);
+        for (idx, (row, op)) in rows.iter().zip(ops).enumerate() {
+            assert_eq!(
+                (row.rw_counter(), row.is_write()),
+                op,
+                "{:?} row {} doesn't match its operation",
+                target,
+                idx
+            );
+        }
+    }
+}
+
+/// Check the read at `idx` of the sorted `rows` the way
+/// [`RwMap::check_value`] does.
+fn read_value_error(
+    idx: usize,
+    rows: &[Rw],
+    is_first: bool,
+    updates: &MptUpdates,
+) -> Option<ValueError> {
+    let row = &rows[idx];
+    let prev_row = &rows[idx - 1];
+    if row.is_write() {
+        return None;
+    }
+    let value = row.value_assignment();
+    let reason = if is_first {
+        // value == init_value
+        let init_value = updates
+            .get(row)
+            .map(|u| u.value_assignments().1)
+            .unwrap_or_default();
+        if value == init_value {
+            return None;
+        }
+        "first access reads don't change value"
+    } else {
+        // value == prev_value
+        if value == prev_row.value_assignment() {
+            return None;
+        }
+        "non-first access reads don't change value"
+    };
+    Some(ValueError {
+        idx,
+        reason,
+        row: *row,
+        prev_row: *prev_row,
+    })
+}
+
+/// Maximum value of a `CallContext` field, `None` if it is unbounded.
+fn call_context_max(field_tag: CallContextFieldTag) -> Option<Word> {
+    match field_tag {
+        CallContextFieldTag::Depth => Some(Word::from(1024)),
+        CallContextFieldTag::StackPointer => Some(Word::from(1023)),
+        CallContextFieldTag::CallerAddress | CallContextFieldTag::CalleeAddress => {
+            Some((Word::one() << 160) - 1)
+        }
+        CallContextFieldTag::CallDataOffset
+        | CallContextFieldTag::CallDataLength
+        | CallContextFieldTag::ReturnDataOffset
+        | CallContextFieldTag::ReturnDataLength
+        | CallContextFieldTag::LastCalleeReturnDataOffset
+        | CallContextFieldTag::LastCalleeReturnDataLength => Some(Word::from(u64::MAX)),
+        CallContextFieldTag::IsSuccess
+        | CallContextFieldTag::IsPersistent
+        | CallContextFieldTag::IsStatic
+        | CallContextFieldTag::IsRoot
+        | CallContextFieldTag::IsCreate => Some(Word::one()),
+        _ => None,
+    }
+}
+
+/// Whether a `CallContext` field is set up once when the call starts and
+/// never changes afterwards.
+fn is_immutable_call_context_field(field_tag: CallContextFieldTag) -> bool {
+    matches!(
+        field_tag,
+        CallContextFieldTag::RwCounterEndOfReversion
+            | CallContextFieldTag::CallerId
+            | CallContextFieldTag::TxId
+            | CallContextFieldTag::Depth
+            | CallContextFieldTag::CallerAddress
+            | CallContextFieldTag::CalleeAddress
+            | CallContextFieldTag::CallDataOffset
+            | CallContextFieldTag::CallDataLength
+            | CallContextFieldTag::ReturnDataOffset
+            | CallContextFieldTag::ReturnDataLength
+            | CallContextFieldTag::Value
+            | CallContextFieldTag::IsSuccess
+            | CallContextFieldTag::IsPersistent
+            | CallContextFieldTag::IsStatic
+            | CallContextFieldTag::IsRoot
+            | CallContextFieldTag::IsCreate
+            | CallContextFieldTag::CodeHash
+    )
+}
+
+/// The columns of a row in the dumps of [`RwMap::to_hex_dump`] and
+/// [`RwMap::to_interned_binary`]: tag, rw_counter, is_write, id, address,
+/// field_tag, storage_key, value, value_prev and init_val.
+fn dump_columns(row: &Rw) -> [Word; 10] {
+    [
+        Word::from(row.tag() as u64),
+        Word::from(row.rw_counter().0 as u64),
+        Word::from(row.is_write() as u64),
+        Word::from(row.id().unwrap_or_default() as u64),
+        row.address().unwrap_or_default().to_word(),
+        Word::from(row.field_tag().unwrap_or_default()),
+        row.storage_key().unwrap_or_default(),
+        row.value_assignment(),
+        row.value_prev_assignment().unwrap_or_default(),
+        row.committed_value().unwrap_or_default(),
+    ]
+}
+
+/// Whether the rows of `target` have an account address, which is interned by
+/// [`RwMap::to_interned_binary`].
+fn has_account_address(target: Target) -> bool {
+    matches!(
+        target,
+        Target::TxAccessListAccount
+            | Target::TxAccessListAccountStorage
+            | Target::Account
+            | Target::Storage
+    )
+}
+
+/// Append `word` to the bytes of [`RwMap::to_interned_binary`] as its length
+/// followed by its big-endian bytes without the leading zeros.
+fn push_binary_word(bytes: &mut Vec<u8>, word: &Word) {
+    let mut be_bytes = [0u8; 32];
+    word.to_big_endian(&mut be_bytes);
+    let len = (word.bits() + 7) / 8;
+    bytes.push(len as u8);
+    bytes.extend(&be_bytes[32 - len..]);
+}
+
+/// Cursor over the bytes of [`RwMap::to_interned_binary`].
+struct DumpReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> DumpReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < len {
+            return Err(format!("unexpected end of input, {len} bytes needed"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn word(&mut self) -> Result<Word, String> {
+        let len = self.u8()? as usize;
+        if len > 32 {
+            return Err(format!("word of {len} bytes"));
+        }
+        Ok(Word::from_big_endian(self.take(len)?))
+    }
+}
+
+/// Parse one line of [`RwMap::to_hex_dump`] back into a row.
+fn parse_hex_dump_row(text: &str) -> Result<Rw, String> {
+    let columns = text
+        .split(':')
+        .map(|field| Word::from_str_radix(field, 16).map_err(|_| format!("invalid hex {field}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let columns: [Word; 10] = columns
+        .try_into()
+        .map_err(|columns: Vec<Word>| format!("expected 10 fields, found {}", columns.len()))?;
+    row_from_dump_columns(columns)
+}
+
+/// Convert a column of a dump to a `usize`, failing instead of panicking
+/// like `Word::as_usize` if it doesn't fit.
+fn dump_usize(word: Word, column: &str) -> Result<usize, String> {
+    if word.bits() > usize::BITS as usize {
+        return Err(format!("{column} {word:x} exceeds usize"));
+    }
+    Ok(word.as_usize())
+}
+
+/// Inverse of [`dump_columns`].
+fn row_from_dump_columns(columns: [Word; 10]) -> Result<Rw, String> {
+    let [tag, rw_counter, is_write, id, address, field_tag, ..] = columns;
+    let [.., storage_key, value, value_prev, init_val] = columns;
+    let tag = Target::iter()
+        .find(|target| *target as u64 == tag.low_u64())
+        .ok_or_else(|| format!("unknown tag {tag}"))?;
+    let rw_counter = RwCounter(dump_usize(rw_counter, "rw_counter")?);
+    let is_write = !is_write.is_zero();
+    let id = dump_usize(id, "id")?;
+    let field_tag = field_tag.low_u64();
+    let unknown_field_tag = || format!("unknown field tag {field_tag} for {tag:?}");
+    Ok(match tag {
+        Target::Start => Rw::Start { rw_counter },
+        Target::TxAccessListAccount => Rw::TxAccessListAccount {
+            rw_counter,
+            is_write,
+            tx_id: id,
+            account_address: address.to_address(),
+            is_warm: !value.is_zero(),
+            is_warm_prev: !value_prev.is_zero(),
+        },
+        Target::TxAccessListAccountStorage => Rw::TxAccessListAccountStorage {
+            rw_counter,
+            is_write,
+            tx_id: id,
+            account_address: address.to_address(),
+            storage_key,
+            is_warm: !value.is_zero(),
+            is_warm_prev: !value_prev.is_zero(),
+        },
+        Target::TxRefund => Rw::TxRefund {
+            rw_counter,
+            is_write,
+            tx_id: id,
+            value: value.low_u64(),
+            value_prev: value_prev.low_u64(),
+        },
+        Target::Account => Rw::Account {
+            rw_counter,
+            is_write,
+            account_address: address.to_address(),
+            field_tag: AccountFieldTag::iter()
+                .find(|tag| *tag as u64 == field_tag)
+                .ok_or_else(unknown_field_tag)?,
+            value,
+            value_prev,
+        },
+        Target::Storage => Rw::AccountStorage {
+            rw_counter,
+            is_write,
+            account_address: address.to_address(),
+            storage_key,
+            value,
+            value_prev,
+            tx_id: id,
+            committed_value: init_val,
+        },
+        Target::CallContext => Rw::CallContext {
+            rw_counter,
+            is_write,
+            call_id: id,
+            field_tag: CallContextFieldTag::iter()
+                .find(|tag| *tag as u64 == field_tag)
+                .ok_or_else(unknown_field_tag)?,
+            value,
+        },
+        Target::Stack => Rw::Stack {
+            rw_counter,
+            is_write,
+            call_id: id,
+            stack_pointer: dump_usize(address, "stack pointer")?,
+            value,
+        },
+        Target::Memory => Rw::Memory {
+            rw_counter,
+            is_write,
+            call_id: id,
+            memory_address: address.low_u64(),
+            byte: value.low_u64() as u8,
+        },
+        Target::TxLog => {
+            let (index, field_tag, log_id) = try_decode_tx_log_address(address.to_address())
+                .ok_or_else(|| format!("unknown tx log field tag in address {address:x}"))?;
+            Rw::TxLog {
+                rw_counter,
+                is_write,
+                tx_id: id,
+                log_id,
+                field_tag,
+                index: index as usize,
+                value,
+            }
+        }
+        Target::TxReceipt => Rw::TxReceipt {
+            rw_counter,
+            is_write,
+            tx_id: id,
+            field_tag: TxReceiptFieldTag::iter()
+                .find(|tag| *tag as u64 == field_tag)
+                .ok_or_else(unknown_field_tag)?,
+            value: value.low_u64(),
+        },
+    })
+}
+
+/// Total number of operations in the container.
+fn container_len(container: &operation::OperationContainer) -> usize {
+    container.memory.len()
+        + container.stack.len()
+        + container.storage.len()
+        + container.tx_access_list_account.len()
+        + container.tx_access_list_account_storage.len()
+        + container.tx_refund.len()
+        + container.account.len()
+        + container.call_context.len()
+        + container.tx_receipt.len()
+        + container.tx_log.len()
+        + container.start.len()
+}
+
+/// The rw_counter and is_write flag of the operations of a single `target` in
+/// the container.
+#[cfg(any(test, feature = "test-util"))]
+fn container_counters(
+    container: &operation::OperationContainer,
+    target: Target,
+) -> Vec<(RwCounter, bool)> {
+    match target {
+        Target::Start => container
+            .start
+            .iter()
+            .map(|op| (op.rwc().into(), false))
+            .collect(),
+        Target::Memory => container
+            .memory
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::Stack => container
+            .stack
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::Storage => container
+            .storage
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::TxAccessListAccount => container
+            .tx_access_list_account
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::TxAccessListAccountStorage => container
+            .tx_access_list_account_storage
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::TxRefund => container
+            .tx_refund
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::Account => container
+            .account
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::CallContext => container
+            .call_context
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::TxReceipt => container
+            .tx_receipt
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+        Target::TxLog => container
+            .tx_log
+            .iter()
+            .map(|op| (op.rwc().into(), op.rw().is_write()))
+            .collect(),
+    }
+}
+
+/// Convert the operations of a single `target` in the container into rows.
+fn container_rows(container: &operation::OperationContainer, target: Target) -> Vec<Rw> {
+    match target {
+        Target::Start => container
+            .start
+            .iter()
+            .map(|op| Rw::Start {
+                rw_counter: op.rwc().into(),
+            })
+            .collect(),
+        Target::TxAccessListAccount => container
+            .tx_access_list_account
+            .iter()
+            .map(|op| Rw::TxAccessListAccount {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                tx_id: op.op().tx_id,
+                account_address: op.op().address,
+                is_warm: op.op().is_warm,
+                is_warm_prev: op.op().is_warm_prev,
+            })
+            .collect(),
+        Target::TxAccessListAccountStorage => container
+            .tx_access_list_account_storage
+            .iter()
+            .map(|op| Rw::TxAccessListAccountStorage {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                tx_id: op.op().tx_id,
+                account_address: op.op().address,
+                storage_key: op.op().key,
+                is_warm: op.op().is_warm,
+                is_warm_prev: op.op().is_warm_prev,
+            })
+            .collect(),
+        Target::TxRefund => container
+            .tx_refund
+            .iter()
+            .map(|op| Rw::TxRefund {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                tx_id: op.op().tx_id,
+                value: op.op().value,
+                value_prev: op.op().value_prev,
+            })
+            .collect(),
+        Target::Account => container
+            .account
+            .iter()
+            .map(|op| Rw::Account {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                account_address: op.op().address,
+                field_tag: match op.op().field {
+                    AccountField::Nonce => AccountFieldTag::Nonce,
+                    AccountField::Balance => AccountFieldTag::Balance,
+                    AccountField::CodeHash => AccountFieldTag::CodeHash,
+                },
+                value: op.op().value,
+                value_prev: op.op().value_prev,
+            })
+            .collect(),
+        Target::Storage => container
+            .storage
+            .iter()
+            .map(|op| Rw::AccountStorage {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                account_address: op.op().address,
+                storage_key: op.op().key,
+                value: op.op().value,
+                value_prev: op.op().value_prev,
+                tx_id: op.op().tx_id,
+                committed_value: op.op().committed_value,
+            })
+            .collect(),
+        Target::CallContext => container
+            .call_context
+            .iter()
+            .map(|op| Rw::CallContext {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                call_id: op.op().call_id,
+                field_tag: match op.op().field {
+                    CallContextField::RwCounterEndOfReversion => {
+                        CallContextFieldTag::RwCounterEndOfReversion
+                    }
+                    CallContextField::CallerId => CallContextFieldTag::CallerId,
+                    CallContextField::TxId => CallContextFieldTag::TxId,
+                    CallContextField::Depth => CallContextFieldTag::Depth,
+                    CallContextField::CallerAddress => CallContextFieldTag::CallerAddress,
+                    CallContextField::CalleeAddress => CallContextFieldTag::CalleeAddress,
+                    CallContextField::CallDataOffset => CallContextFieldTag::CallDataOffset,
+                    CallContextField::CallDataLength => CallContextFieldTag::CallDataLength,
+                    CallContextField::ReturnDataOffset => CallContextFieldTag::ReturnDataOffset,
+                    CallContextField::ReturnDataLength => CallContextFieldTag::ReturnDataLength,
+                    CallContextField::Value => CallContextFieldTag::Value,
+                    CallContextField::IsSuccess => CallContextFieldTag::IsSuccess,
+                    CallContextField::IsPersistent => CallContextFieldTag::IsPersistent,
+                    CallContextField::IsStatic => CallContextFieldTag::IsStatic,
+                    CallContextField::LastCalleeId => CallContextFieldTag::LastCalleeId,
+                    CallContextField::LastCalleeReturnDataOffset => {
+                        CallContextFieldTag::LastCalleeReturnDataOffset
+                    }
+                    CallContextField::LastCalleeReturnDataLength => {
+                        CallContextFieldTag::LastCalleeReturnDataLength
+                    }
+                    CallContextField::IsRoot => CallContextFieldTag::IsRoot,
+                    CallContextField::IsCreate => CallContextFieldTag::IsCreate,
+                    CallContextField::CodeHash => CallContextFieldTag::CodeHash,
+                    CallContextField::ProgramCounter => CallContextFieldTag::ProgramCounter,
+                    CallContextField::StackPointer => CallContextFieldTag::StackPointer,
+                    CallContextField::GasLeft => CallContextFieldTag::GasLeft,
+                    CallContextField::MemorySize => CallContextFieldTag::MemorySize,
+                    CallContextField::ReversibleWriteCounter => {
+                        CallContextFieldTag::ReversibleWriteCounter
+                    }
+                },
+                value: op.op().value,
+            })
+            .collect(),
+        Target::Stack => container
+            .stack
+            .iter()
+            .map(|op| Rw::Stack {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                call_id: op.op().call_id(),
+                stack_pointer: usize::from(*op.op().address()),
+                value: *op.op().value(),
+            })
+            .collect(),
+        Target::Memory => container
+            .memory
+            .iter()
+            .map(|op| Rw::Memory {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                call_id: op.op().call_id(),
+                memory_address: u64::from_le_bytes(
+                    op.op().address().to_le_bytes()[..8].try_into().unwrap(),
+                ),
+                byte: op.op().value(),
+            })
+            .collect(),
+        Target::TxLog => container
+            .tx_log
+            .iter()
+            .map(|op| Rw::TxLog {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                tx_id: op.op().tx_id,
+                log_id: op.op().log_id as u64,
+                field_tag: match op.op().field {
+                    TxLogField::Address => TxLogFieldTag::Address,
+                    TxLogField::Topic => TxLogFieldTag::Topic,
+                    TxLogField::Data => TxLogFieldTag::Data,
+                },
+                index: op.op().index,
+                value: op.op().value,
+            })
+            .collect(),
+        Target::TxReceipt => container
+            .tx_receipt
+            .iter()
+            .map(|op| Rw::TxReceipt {
+                rw_counter: op.rwc().into(),
+                is_write: op.rw().is_write(),
+                tx_id: op.op().tx_id,
+                field_tag: match op.op().field {
+                    TxReceiptField::PostStateOrStatus => TxReceiptFieldTag::PostStateOrStatus,
+                    TxReceiptField::LogLength => TxReceiptFieldTag::LogLength,
+                    TxReceiptField::CumulativeGasUsed => TxReceiptFieldTag::CumulativeGasUsed,
+                },
+                value: op.op().value,
+            })
+            .collect(),
     }
 }
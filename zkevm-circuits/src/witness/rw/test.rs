@@ -0,0 +1,2636 @@
+use super::*;
+use bus_mapping::operation::{
+    MemoryOp, Operation, OperationContainer, RWCounter, StackOp, StorageOp, RW,
+};
+use eth_types::evm_types::{MemoryAddress, StackAddress};
+use halo2_proofs::halo2curves::bn256::Fr;
+
+fn storage_write(rw_counter: usize, value: u64, value_prev: u64) -> Rw {
+    Rw::AccountStorage {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address: Address::zero(),
+        storage_key: Word::from(1),
+        value: Word::from(value),
+        value_prev: Word::from(value_prev),
+        tx_id: 1,
+        committed_value: Word::zero(),
+    }
+}
+
+/// One write row of each variant, with rw_counters following their order
+fn one_row_of_each_variant() -> Vec<Rw> {
+    vec![
+        Rw::Start {
+            rw_counter: RwCounter(1),
+        },
+        Rw::TxAccessListAccount {
+            rw_counter: RwCounter(2),
+            is_write: true,
+            tx_id: 1,
+            account_address: Address::zero(),
+            is_warm: true,
+            is_warm_prev: false,
+        },
+        Rw::TxAccessListAccountStorage {
+            rw_counter: RwCounter(3),
+            is_write: true,
+            tx_id: 1,
+            account_address: Address::zero(),
+            storage_key: Word::one(),
+            is_warm: true,
+            is_warm_prev: false,
+        },
+        Rw::TxRefund {
+            rw_counter: RwCounter(4),
+            is_write: true,
+            tx_id: 1,
+            value: 15000,
+            value_prev: 0,
+        },
+        Rw::Account {
+            rw_counter: RwCounter(5),
+            is_write: true,
+            account_address: Address::zero(),
+            field_tag: AccountFieldTag::Nonce,
+            value: Word::one(),
+            value_prev: Word::zero(),
+        },
+        storage_write(6, 1, 0),
+        Rw::CallContext {
+            rw_counter: RwCounter(7),
+            is_write: true,
+            call_id: 1,
+            field_tag: CallContextFieldTag::TxId,
+            value: Word::one(),
+        },
+        Rw::Stack {
+            rw_counter: RwCounter(8),
+            is_write: true,
+            call_id: 1,
+            stack_pointer: 1023,
+            value: Word::from(0x20),
+        },
+        Rw::Memory {
+            rw_counter: RwCounter(9),
+            is_write: true,
+            call_id: 1,
+            memory_address: 0x20,
+            byte: 0xab,
+        },
+        Rw::TxLog {
+            rw_counter: RwCounter(10),
+            is_write: true,
+            tx_id: 1,
+            log_id: 1,
+            field_tag: TxLogFieldTag::Address,
+            index: 0,
+            value: Word::one(),
+        },
+        Rw::TxReceipt {
+            rw_counter: RwCounter(11),
+            is_write: true,
+            tx_id: 1,
+            field_tag: TxReceiptFieldTag::PostStateOrStatus,
+            value: 1,
+        },
+    ]
+}
+
+fn mock_container() -> OperationContainer {
+    OperationContainer {
+        stack: vec![
+            Operation::new(
+                RWCounter::from(1),
+                RW::WRITE,
+                StackOp::new(1, StackAddress::from(1023), Word::from(0x20)),
+            ),
+            Operation::new(
+                RWCounter::from(4),
+                RW::READ,
+                StackOp::new(1, StackAddress::from(1023), Word::from(0x20)),
+            ),
+        ],
+        memory: vec![
+            Operation::new(
+                RWCounter::from(2),
+                RW::WRITE,
+                MemoryOp::new(1, MemoryAddress::from(0), 0x20),
+            ),
+            Operation::new(
+                RWCounter::from(5),
+                RW::READ,
+                MemoryOp::new(1, MemoryAddress::from(0), 0x20),
+            ),
+        ],
+        storage: vec![Operation::new(
+            RWCounter::from(3),
+            RW::WRITE,
+            StorageOp::new(
+                Address::zero(),
+                Word::from(0x1),
+                Word::from(0x2),
+                Word::zero(),
+                1,
+                Word::zero(),
+            ),
+        )],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn from_container_targets_skips_other_targets() {
+    let container = mock_container();
+    let rw_map = RwMap::from_container_targets(&container, &[Target::Stack, Target::Memory]);
+
+    assert_eq!(rw_map.0[&Target::Stack].len(), 2);
+    assert_eq!(rw_map.0[&Target::Memory].len(), 2);
+    for target in Target::iter().filter(|t| !matches!(t, Target::Stack | Target::Memory)) {
+        assert!(rw_map.0.get(&target).map_or(true, Vec::is_empty));
+    }
+}
+
+#[test]
+fn explain_cell_resolves_padded_offset() {
+    let rw_map = RwMap::from(&mock_container());
+
+    // 5 real rows padded to 10: offsets 0..5 are Start rows, followed by the
+    // memory rows sorted by (call_id, address, rw_counter).
+    let (row, value) = rw_map.explain_cell::<Fr>(5, "rw_counter", 10).unwrap();
+    assert!(matches!(
+        row,
+        Rw::Memory {
+            rw_counter: RwCounter(2),
+            ..
+        }
+    ));
+    assert_eq!(value, Fr::from(2u64));
+
+    let (row, value) = rw_map.explain_cell::<Fr>(6, "value_lo", 10).unwrap();
+    assert!(matches!(
+        row,
+        Rw::Memory {
+            rw_counter: RwCounter(5),
+            ..
+        }
+    ));
+    assert_eq!(value, Fr::from(0x20u64));
+
+    let cells = row.table_assignment::<Fr>().unwrap().as_advice_cells();
+    assert_eq!(cells[8], ("value_lo", Fr::from(0x20u64)));
+
+    assert!(rw_map.explain_cell::<Fr>(10, "rw_counter", 10).is_none());
+    assert!(rw_map.explain_cell::<Fr>(5, "unknown", 10).is_none());
+    // 5 real rows leave no room for the Start row in a table of 5.
+    assert!(rw_map.explain_cell::<Fr>(0, "rw_counter", 5).is_none());
+    assert!(rw_map.explain_cell::<Fr>(0, "rw_counter", 3).is_none());
+}
+
+#[test]
+fn fold_rows_counts_writes() {
+    let rw_map = RwMap::from(&mock_container());
+    let writes = rw_map.fold_rows(0, |acc, row| acc + row.is_write() as usize);
+    assert_eq!(writes, 3);
+}
+
+#[test]
+fn real_rows_strips_padding() {
+    let mut rw_map = RwMap::from(&mock_container());
+    let rows = rw_map.table_assignments();
+
+    let (padded, padding_len) = RwMap::table_assignments_prepad(&rows, 10);
+    assert_eq!(padding_len, 5);
+    rw_map
+        .0
+        .insert(Target::Start, padded[..padding_len].to_vec());
+
+    assert_eq!(rw_map.table_assignments().len(), 10);
+    assert_eq!(rw_map.real_rows(), rows);
+}
+
+#[test]
+fn chunk_sorted_keeps_groups_intact() {
+    let rw_map = RwMap::from(&mock_container());
+    let rows = rw_map.table_assignments();
+
+    for k in 1..=rows.len() + 1 {
+        let chunks = rw_map.chunk_sorted(k);
+        assert!(chunks.len() <= k);
+        assert_eq!(
+            chunks.concat(),
+            rows,
+            "chunks should partition the sorted rows"
+        );
+        for (chunk, next) in chunks.iter().tuple_windows() {
+            assert_ne!(chunk.last().unwrap().key(), next[0].key());
+        }
+    }
+}
+
+#[test]
+fn check_value_prev_chain_detects_stale_prev() {
+    let valid = RwMap::from_rows(vec![
+        storage_write(1, 1, 0),
+        storage_write(2, 2, 1),
+        storage_write(3, 3, 2),
+    ]);
+    assert!(valid.check_value_prev_chain().is_ok());
+
+    let broken = RwMap::from_rows(vec![
+        storage_write(1, 1, 0),
+        storage_write(2, 2, 1),
+        storage_write(3, 3, 5),
+    ]);
+    let errs = broken.check_value_prev_chain().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].idx, 2);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(3));
+    assert_eq!(errs[0].prev_row.rw_counter(), RwCounter(2));
+}
+
+#[test]
+fn referenced_code_hashes_from_account_and_call_context() {
+    let empty_code_hash = CodeDB::empty_code_hash().to_word();
+    let rw_map = RwMap::from_rows(vec![
+        Rw::Account {
+            rw_counter: RwCounter(1),
+            is_write: true,
+            account_address: Address::repeat_byte(0xaa),
+            field_tag: AccountFieldTag::CodeHash,
+            value: Word::from(0x1111),
+            value_prev: Word::zero(),
+        },
+        Rw::Account {
+            rw_counter: RwCounter(2),
+            is_write: false,
+            account_address: Address::repeat_byte(0xbb),
+            field_tag: AccountFieldTag::CodeHash,
+            value: empty_code_hash,
+            value_prev: empty_code_hash,
+        },
+        Rw::CallContext {
+            rw_counter: RwCounter(3),
+            is_write: false,
+            call_id: 1,
+            field_tag: CallContextFieldTag::CodeHash,
+            value: Word::from(0x2222),
+        },
+        Rw::CallContext {
+            rw_counter: RwCounter(4),
+            is_write: false,
+            call_id: 1,
+            field_tag: CallContextFieldTag::Depth,
+            value: Word::from(0x3333),
+        },
+    ]);
+
+    assert_eq!(
+        rw_map.referenced_code_hashes(false),
+        BTreeSet::from([Word::from(0x1111), Word::from(0x2222)])
+    );
+    assert_eq!(
+        rw_map.referenced_code_hashes(true),
+        BTreeSet::from([
+            Word::zero(),
+            Word::from(0x1111),
+            Word::from(0x2222),
+            empty_code_hash
+        ])
+    );
+}
+
+#[test]
+fn rlc_multiset_is_order_independent() {
+    let rows = RwMap::from(&mock_container()).table_assignments();
+    let forward = RwMap::from_rows(rows.clone());
+    let backward = RwMap::from_rows(rows.into_iter().rev());
+
+    let randomness = Fr::from(0x100u64);
+    let mut forward_rlcs = forward.rlc_multiset(randomness);
+    let mut backward_rlcs = backward.rlc_multiset(randomness);
+    // 5 rows plus a single Start padding row
+    assert_eq!(forward_rlcs.len(), 6);
+    forward_rlcs.sort();
+    backward_rlcs.sort();
+    assert_eq!(forward_rlcs, backward_rlcs);
+}
+
+#[test]
+fn check_stack_init_detects_pop_of_unpushed_slot() {
+    let rw_map = RwMap::from(&mock_container());
+    assert!(rw_map.check_stack_init().is_ok());
+
+    let mut container = mock_container();
+    container.stack.push(Operation::new(
+        RWCounter::from(6),
+        RW::READ,
+        StackOp::new(1, StackAddress::from(1022), Word::from(0x20)),
+    ));
+    let errs = RwMap::from(&container).check_stack_init().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].call_id, 1);
+    assert_eq!(errs[0].stack_pointer, 1022);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(6));
+}
+
+#[test]
+fn chunks_by_step_splits_at_boundaries() {
+    let mut rw_map = RwMap::from_rows((1..=10).rev().map(|rw_counter| Rw::Stack {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1024 - rw_counter,
+        value: Word::from(rw_counter),
+    }));
+    // Start padding is not part of any step.
+    rw_map.0.insert(
+        Target::Start,
+        vec![
+            Rw::Start {
+                rw_counter: RwCounter(1),
+            },
+            Rw::Start {
+                rw_counter: RwCounter(2),
+            },
+        ],
+    );
+
+    let steps = rw_map.chunks_by_step(&[3, 7]);
+    let counters: Vec<Vec<usize>> = steps
+        .iter()
+        .map(|step| step.iter().map(|row| row.rw_counter().0).collect())
+        .collect();
+    assert_eq!(
+        counters,
+        vec![vec![1, 2], vec![3, 4, 5, 6], vec![7, 8, 9, 10]]
+    );
+}
+
+#[test]
+fn try_from_container_limited_rejects_oversized_container() {
+    let container = mock_container();
+    assert_eq!(
+        RwMap::try_from_container_limited(&container, 4).unwrap_err(),
+        TooManyRows {
+            rows: 5,
+            max_rows: 4
+        }
+    );
+
+    let rw_map = RwMap::try_from_container_limited(&container, 5).unwrap();
+    assert_eq!(rw_map.table_assignments().len(), 5);
+}
+
+#[test]
+fn from_interned_binary_rejects_short_body_before_allocating() {
+    // Reserving room for u32::MAX addresses or rows up front would abort on
+    // allocation instead of returning the errors below.
+    let mut bytes = u32::MAX.to_le_bytes().to_vec();
+    bytes.extend(Address::zero().as_bytes());
+    let err = RwMap::from_interned_binary(&bytes).unwrap_err();
+    assert_eq!(err.line, 0);
+    assert!(err.message.contains("unexpected end of input"));
+
+    let mut bytes = 0u32.to_le_bytes().to_vec();
+    bytes.extend(u32::MAX.to_le_bytes());
+    bytes.push(Target::Stack as u8);
+    let err = RwMap::from_interned_binary(&bytes).unwrap_err();
+    assert_eq!(err.line, 1);
+    assert!(err.message.contains("unexpected end of input"));
+}
+
+#[test]
+fn final_account_state_takes_last_write() {
+    let address = Address::repeat_byte(0xaa);
+    let account = |rw_counter, is_write, field_tag, value: u64, value_prev: u64| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        account_address: address,
+        field_tag,
+        value: Word::from(value),
+        value_prev: Word::from(value_prev),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        account(1, false, AccountFieldTag::Balance, 100, 100),
+        account(2, true, AccountFieldTag::Balance, 70, 100),
+        account(3, false, AccountFieldTag::Nonce, 0, 0),
+        account(4, true, AccountFieldTag::Nonce, 1, 0),
+        account(5, false, AccountFieldTag::Balance, 70, 70),
+        account(6, false, AccountFieldTag::CodeHash, 0x1111, 0x1111),
+    ]);
+
+    let accounts = rw_map.final_account_state();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(
+        accounts[&address],
+        AccountSummary {
+            nonce: Some(Word::from(1)),
+            balance: Some(Word::from(70)),
+            code_hash: Some(Word::from(0x1111)),
+        }
+    );
+}
+
+#[test]
+fn assert_deterministic_on_mock_container() {
+    assert_deterministic(&mock_container());
+}
+
+#[test]
+fn writes_only_drops_reads_and_start() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::Start,
+        vec![Rw::Start {
+            rw_counter: RwCounter(1),
+        }],
+    );
+
+    let writes = rw_map.writes_only();
+    assert!(writes.0[&Target::Start].is_empty());
+    let counters: Vec<usize> = writes
+        .table_assignments()
+        .iter()
+        .map(|row| row.rw_counter().0)
+        .sorted()
+        .collect();
+    assert_eq!(counters, vec![1, 2, 3]);
+}
+
+#[test]
+fn committed_value_only_for_storage() {
+    let stack = Rw::Stack {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::from(0x20),
+    };
+    assert_eq!(stack.committed_value(), None);
+
+    let storage = Rw::AccountStorage {
+        rw_counter: RwCounter(2),
+        is_write: true,
+        account_address: Address::zero(),
+        storage_key: Word::from(1),
+        value: Word::from(2),
+        value_prev: Word::from(1),
+        tx_id: 1,
+        committed_value: Word::from(0x7),
+    };
+    assert_eq!(storage.committed_value(), Some(Word::from(0x7)));
+}
+
+#[test]
+fn nth_access_counter_in_counter_order() {
+    let rw_map = RwMap::from_rows(vec![
+        storage_write(9, 3, 2),
+        storage_write(2, 1, 0),
+        storage_write(5, 2, 1),
+    ]);
+    let key = storage_write(0, 0, 0).key();
+
+    assert_eq!(rw_map.nth_access_counter(key, 0), Some(2));
+    assert_eq!(rw_map.nth_access_counter(key, 1), Some(5));
+    assert_eq!(rw_map.nth_access_counter(key, 2), Some(9));
+    assert_eq!(rw_map.nth_access_counter(key, 3), None);
+    assert_eq!(
+        rw_map.nth_access_counter(
+            RwKey {
+                storage_key: Word::from(2),
+                ..key
+            },
+            0
+        ),
+        None
+    );
+}
+
+#[test]
+fn merge_sorted_merges_sorted_chunks() {
+    let rows = RwMap::from(&mock_container()).table_assignments();
+    let chunks = vec![
+        vec![rows[0], rows[3]],
+        vec![rows[1], rows[2]],
+        vec![rows[4]],
+    ];
+    assert_eq!(merge_sorted(chunks), rows);
+}
+
+#[test]
+fn value_at_between_writes() {
+    let rw_map = RwMap::from_rows(vec![storage_write(2, 1, 0), storage_write(5, 2, 1)]);
+    let key = storage_write(0, 0, 0).key();
+
+    assert_eq!(rw_map.value_at(key, 1), Some(Word::zero()));
+    assert_eq!(rw_map.value_at(key, 2), Some(Word::from(1)));
+    assert_eq!(rw_map.value_at(key, 4), Some(Word::from(1)));
+    assert_eq!(rw_map.value_at(key, 5), Some(Word::from(2)));
+    assert_eq!(
+        rw_map.value_at(
+            RwKey {
+                storage_key: Word::from(2),
+                ..key
+            },
+            5
+        ),
+        None
+    );
+}
+
+#[test]
+fn check_nonce_monotone_detects_decrease() {
+    let address = Address::repeat_byte(0xaa);
+    let nonce_write = |rw_counter, value: u64, value_prev: u64| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address: address,
+        field_tag: AccountFieldTag::Nonce,
+        value: Word::from(value),
+        value_prev: Word::from(value_prev),
+    };
+
+    let valid = RwMap::from_rows(vec![nonce_write(1, 1, 0), nonce_write(2, 2, 1)]);
+    assert!(valid.check_nonce_monotone().is_ok());
+
+    let decreasing = RwMap::from_rows(vec![nonce_write(1, 2, 1), nonce_write(2, 1, 2)]);
+    let errs = decreasing.check_nonce_monotone().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].account_address, address);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(2));
+    assert_eq!(errs[0].prev_value, Word::from(2));
+}
+
+#[test]
+fn timeline_buckets_targets() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::Start,
+        vec![Rw::Start {
+            rw_counter: RwCounter(0),
+        }],
+    );
+    rw_map.0.insert(
+        Target::TxRefund,
+        vec![Rw::TxRefund {
+            rw_counter: RwCounter(12),
+            is_write: true,
+            tx_id: 1,
+            value: 1,
+            value_prev: 0,
+        }],
+    );
+
+    assert_eq!(
+        rw_map.timeline(5),
+        vec![
+            (
+                1..=5,
+                BTreeSet::from([Target::Memory, Target::Stack, Target::Storage])
+            ),
+            (6..=10, BTreeSet::new()),
+            (11..=15, BTreeSet::from([Target::TxRefund])),
+        ]
+    );
+}
+
+#[test]
+fn timeline_puts_counter_zero_in_first_window() {
+    let rw_map = RwMap::from_rows(vec![storage_write(0, 1, 0)]);
+    assert_eq!(
+        rw_map.timeline(5),
+        vec![(1..=5, BTreeSet::from([Target::Storage]))]
+    );
+}
+
+#[test]
+#[should_panic(expected = "timeline bucket size must be positive")]
+fn timeline_rejects_empty_bucket() {
+    RwMap::from(&mock_container()).timeline(0);
+}
+
+#[test]
+fn unwrap_rows_reports_unknown_value() {
+    let rows: Vec<RwRow<Value<Fr>>> = RwMap::from(&mock_container())
+        .table_assignments()
+        .iter()
+        .map(|row| row.table_assignment())
+        .collect();
+    assert_eq!(unwrap_rows(rows.clone()).unwrap().len(), 5);
+
+    let mut rows = rows;
+    rows[3].value = word::Word::new([Value::known(Fr::from(0x20u64)), Value::unknown()]);
+    assert_eq!(
+        unwrap_rows(rows).unwrap_err(),
+        UnwrapError {
+            row: 3,
+            column: "value_hi"
+        }
+    );
+}
+
+#[test]
+fn field_tag_name_for_tagged_rows() {
+    let balance = Rw::Account {
+        rw_counter: RwCounter(1),
+        is_write: false,
+        account_address: Address::zero(),
+        field_tag: AccountFieldTag::Balance,
+        value: Word::from(100),
+        value_prev: Word::from(100),
+    };
+    assert_eq!(balance.field_tag_name(), Some("Balance"));
+
+    let gas_left = Rw::CallContext {
+        rw_counter: RwCounter(2),
+        is_write: false,
+        call_id: 1,
+        field_tag: CallContextFieldTag::GasLeft,
+        value: Word::from(21000),
+    };
+    assert_eq!(gas_left.field_tag_name(), Some("GasLeft"));
+
+    assert_eq!(storage_write(3, 1, 0).field_tag_name(), None);
+}
+
+#[test]
+fn table_assignments_presorted_matches_full_sort() {
+    let rows = RwMap::from(&mock_container()).table_assignments();
+    let mut rw_map = RwMap::from_rows(rows.iter().rev().copied());
+    rw_map.sort_buckets();
+    assert_eq!(rw_map.table_assignments_presorted(), rows);
+}
+
+#[test]
+fn from_rows_validated_rejects_duplicate_counter() {
+    let rows = RwMap::from(&mock_container()).table_assignments();
+    let rw_map = RwMap::from_rows_validated(rows.clone()).unwrap();
+    assert_eq!(rw_map.0[&Target::Stack].len(), 2);
+
+    let mut duplicated = rows.clone();
+    duplicated.push(storage_write(3, 4, 2));
+    assert_eq!(
+        RwMap::from_rows_validated(duplicated).unwrap_err(),
+        RwBuildError::DuplicateRwCounter(3)
+    );
+
+    let missing = rows
+        .into_iter()
+        .filter(|row| row.rw_counter() != RwCounter(2))
+        .collect();
+    assert_eq!(
+        RwMap::from_rows_validated(missing).unwrap_err(),
+        RwBuildError::MissingRwCounter(2)
+    );
+
+    let start = Rw::Start {
+        rw_counter: RwCounter(1),
+    };
+    let key = start.key();
+    assert_eq!(
+        RwMap::from_rows_validated(vec![start, start]).unwrap_err(),
+        RwBuildError::DuplicateSortKey { key, rw_counter: 1 }
+    );
+}
+
+#[test]
+fn distinct_storage_slots_per_tx() {
+    let slot_write = |rw_counter, storage_key: u64, tx_id| Rw::AccountStorage {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address: Address::zero(),
+        storage_key: Word::from(storage_key),
+        value: Word::from(1),
+        value_prev: Word::zero(),
+        tx_id,
+        committed_value: Word::zero(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        slot_write(1, 1, 1),
+        slot_write(2, 2, 1),
+        slot_write(3, 1, 1),
+        slot_write(4, 3, 2),
+    ]);
+
+    assert_eq!(rw_map.distinct_storage_slots(1), 2);
+    assert_eq!(rw_map.distinct_storage_slots(2), 1);
+    assert_eq!(rw_map.distinct_storage_slots(3), 0);
+    assert_eq!(rw_map.distinct_storage_slots_all(), 3);
+}
+
+#[test]
+fn hex_dump_round_trip() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::TxLog,
+        vec![Rw::TxLog {
+            rw_counter: RwCounter(6),
+            is_write: true,
+            tx_id: 1,
+            log_id: 2,
+            field_tag: TxLogFieldTag::Topic,
+            index: 3,
+            value: Word::from(0x1234),
+        }],
+    );
+    rw_map.0.insert(
+        Target::CallContext,
+        vec![Rw::CallContext {
+            rw_counter: RwCounter(7),
+            is_write: false,
+            call_id: 1,
+            field_tag: CallContextFieldTag::GasLeft,
+            value: Word::from(21000),
+        }],
+    );
+
+    let dump = rw_map.to_hex_dump();
+    assert_eq!(dump.lines().count(), 7);
+    let parsed = RwMap::from_hex_dump(&dump).unwrap();
+    assert_eq!(parsed.table_assignments(), rw_map.table_assignments());
+    assert_eq!(parsed.to_hex_dump(), dump);
+
+    let err = RwMap::from_hex_dump("03:00000001:1").unwrap_err();
+    assert_eq!(err.line, 1);
+}
+
+#[test]
+fn assign_sequential_counters_follows_order() {
+    let rows = RwMap::from(&mock_container()).table_assignments();
+    let order: Vec<RwKey> = rows
+        .iter()
+        .sorted_by_key(|row| row.rw_counter().0)
+        .map(Rw::key)
+        .collect();
+    let mut rw_map = RwMap::from_rows(rows.iter().map(|row| row.with_rw_counter(RwCounter(0))));
+
+    rw_map.assign_sequential_counters(&order);
+    rw_map.check_rw_counter_sanity();
+    assert!(RwMap::from_rows_validated(rw_map.table_assignments()).is_ok());
+    assert_eq!(rw_map.table_assignments(), rows);
+}
+
+/// The mock container with a stack read that doesn't match the previous write
+/// and a first memory read that doesn't match the initial value.
+fn stale_reads_container() -> OperationContainer {
+    let mut container = mock_container();
+    container.stack.push(Operation::new(
+        RWCounter::from(6),
+        RW::READ,
+        StackOp::new(1, StackAddress::from(1023), Word::from(0x21)),
+    ));
+    container.memory.push(Operation::new(
+        RWCounter::from(7),
+        RW::READ,
+        MemoryOp::new(1, MemoryAddress::from(1), 0x1),
+    ));
+    container
+}
+
+#[test]
+fn value_errors_detects_stale_reads() {
+    let errs = RwMap::from(&stale_reads_container()).value_errors();
+    assert_eq!(
+        errs.iter()
+            .map(|err| (err.row.rw_counter().0, err.reason))
+            .collect::<Vec<_>>(),
+        vec![
+            (7, "first access reads don't change value"),
+            (6, "non-first access reads don't change value"),
+        ]
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn par_value_errors_matches_serial() {
+    // Enough groups, each with a stale read, to span many rayon tasks.
+    let rw_map = RwMap::from_rows((0..512).flat_map(|key| {
+        let storage = |rw_counter, is_write, value: u64| Rw::AccountStorage {
+            rw_counter: RwCounter(rw_counter),
+            is_write,
+            account_address: Address::zero(),
+            storage_key: Word::from(key),
+            value: Word::from(value),
+            value_prev: Word::zero(),
+            tx_id: 1,
+            committed_value: Word::zero(),
+        };
+        [
+            storage(2 * key + 1, true, 1),
+            storage(2 * key + 2, false, 2),
+        ]
+    }));
+    let errs = rw_map.value_errors();
+    assert_eq!(errs.len(), 512);
+    assert_eq!(rw_map.par_value_errors(), errs);
+}
+
+thread_local! {
+    static LOG_RECORDS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Logger counting the records emitted by each test thread
+struct CountingLogger;
+
+impl log::Log for CountingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+    fn log(&self, _record: &log::Record) {
+        LOG_RECORDS.with(|records| records.set(records.get() + 1));
+    }
+    fn flush(&self) {}
+}
+
+static LOGGER: CountingLogger = CountingLogger;
+
+fn log_records_during(f: impl FnOnce()) -> usize {
+    _ = log::set_logger(&LOGGER);
+    log::set_max_level(log::LevelFilter::Trace);
+    let before = LOG_RECORDS.with(|records| records.get());
+    f();
+    LOG_RECORDS.with(|records| records.get()) - before
+}
+
+#[test]
+fn check_value_with_quiet_does_not_log() {
+    let rw_map = RwMap::from(&stale_reads_container());
+
+    let mut errs = vec![];
+    let records = log_records_during(|| errs = rw_map.check_value_with(CheckOptions::quiet()));
+    assert_eq!(records, 0);
+    assert_eq!(errs.len(), 2);
+
+    let records = log_records_during(|| {
+        errs = rw_map.check_value_with(CheckOptions {
+            log: true,
+            max_errors: Some(1),
+        })
+    });
+    assert_eq!(records, 2);
+    assert_eq!(errs.len(), 1);
+}
+
+#[test]
+fn storage_changes_against_pre_state() {
+    let slot = (Address::zero(), Word::from(1));
+    let unchanged_slot = (Address::zero(), Word::from(2));
+    let rw_map = RwMap::from_rows(vec![
+        storage_write(1, 7, 5),
+        storage_write(2, 9, 7),
+        Rw::AccountStorage {
+            rw_counter: RwCounter(3),
+            is_write: true,
+            account_address: Address::zero(),
+            storage_key: Word::from(2),
+            value: Word::from(3),
+            value_prev: Word::from(3),
+            tx_id: 1,
+            committed_value: Word::from(3),
+        },
+    ]);
+    let pre = BTreeMap::from([(slot, Word::from(5)), (unchanged_slot, Word::from(3))]);
+
+    assert_eq!(
+        rw_map.storage_changes(&pre),
+        BTreeMap::from([(slot, (Word::from(5), Word::from(9)))])
+    );
+    assert_eq!(
+        rw_map.storage_changes(&BTreeMap::new()),
+        BTreeMap::from([
+            (slot, (Word::zero(), Word::from(9))),
+            (unchanged_slot, (Word::zero(), Word::from(3))),
+        ])
+    );
+}
+
+#[test]
+fn check_contiguous_per_tx_detects_interleaving() {
+    let refund = |rw_counter, tx_id| Rw::TxRefund {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        tx_id,
+        value: 0,
+        value_prev: 0,
+    };
+    let stack = Rw::Stack {
+        rw_counter: RwCounter(3),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::zero(),
+    };
+
+    let valid = RwMap::from_rows(vec![refund(1, 1), refund(2, 1), stack, refund(4, 2)]);
+    assert!(valid.check_contiguous_per_tx().is_ok());
+
+    let interleaved = RwMap::from_rows(vec![refund(1, 1), refund(2, 2), stack, refund(4, 1)]);
+    assert_eq!(
+        interleaved.check_contiguous_per_tx().unwrap_err(),
+        vec![TxCounterError {
+            tx_id: 1,
+            range: 1..=4,
+            other_tx_id: 2,
+            other_range: 2..=2,
+        }]
+    );
+}
+
+#[test]
+fn renumber_start_rows_after_offset() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::Start,
+        (1..=3)
+            .map(|rw_counter| Rw::Start {
+                rw_counter: RwCounter(rw_counter),
+            })
+            .collect(),
+    );
+
+    rw_map.offset_rw_counters(10);
+    assert_eq!(
+        rw_map.0[&Target::Start]
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![11, 12, 13]
+    );
+
+    rw_map.renumber_start_rows();
+    assert_eq!(
+        rw_map.0[&Target::Start]
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert_eq!(
+        rw_map.0[&Target::Stack]
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![11, 14]
+    );
+}
+
+#[test]
+fn heap_bytes_scales_with_rows() {
+    let stack_map = |n: usize| {
+        RwMap(HashMap::from([(
+            Target::Stack,
+            (1..=n)
+                .map(|rw_counter| Rw::Stack {
+                    rw_counter: RwCounter(rw_counter),
+                    is_write: true,
+                    call_id: 1,
+                    stack_pointer: 1023,
+                    value: Word::zero(),
+                })
+                .collect::<Vec<_>>(),
+        )]))
+    };
+    let empty = stack_map(0).heap_bytes();
+    let small = stack_map(100).heap_bytes();
+    let large = stack_map(200).heap_bytes();
+    assert_eq!(small - empty, 100 * std::mem::size_of::<Rw>());
+    assert_eq!(large - empty, 2 * (small - empty));
+}
+
+#[test]
+fn check_log_id_sequence_detects_gap() {
+    let log = |rw_counter, tx_id, log_id, field_tag| Rw::TxLog {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        tx_id,
+        log_id,
+        field_tag,
+        index: 0,
+        value: Word::zero(),
+    };
+    let valid = RwMap::from_rows(vec![
+        log(1, 1, 1, TxLogFieldTag::Address),
+        log(2, 1, 1, TxLogFieldTag::Topic),
+        log(3, 1, 2, TxLogFieldTag::Address),
+        log(4, 2, 1, TxLogFieldTag::Address),
+    ]);
+    assert!(valid.check_log_id_sequence().is_ok());
+
+    let skipped = RwMap::from_rows(vec![
+        log(1, 1, 1, TxLogFieldTag::Address),
+        log(2, 1, 3, TxLogFieldTag::Address),
+        log(3, 1, 3, TxLogFieldTag::Topic),
+    ]);
+    assert_eq!(
+        skipped.check_log_id_sequence().unwrap_err(),
+        vec![LogIdError {
+            tx_id: 1,
+            log_id: 3,
+            prev_log_id: 1
+        }]
+    );
+}
+
+#[test]
+fn with_tx_id_rewrites_tx_scoped_rows() {
+    let refund = Rw::TxRefund {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        tx_id: 1,
+        value: 10,
+        value_prev: 0,
+    }
+    .with_tx_id(2);
+    assert_eq!(refund.id(), Some(2));
+
+    let stack = Rw::Stack {
+        rw_counter: RwCounter(2),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::from(0x20),
+    };
+    assert_eq!(stack.with_tx_id(2), stack);
+}
+
+#[test]
+fn by_counter_keys_are_contiguous() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::Start,
+        vec![Rw::Start {
+            rw_counter: RwCounter(1),
+        }],
+    );
+    let rows = rw_map.by_counter().unwrap();
+    assert_eq!(
+        rows.keys().copied().collect::<Vec<_>>(),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert!(matches!(rows[&3], Rw::AccountStorage { .. }));
+
+    rw_map
+        .0
+        .get_mut(&Target::Storage)
+        .unwrap()
+        .push(storage_write(2, 1, 0));
+    assert_eq!(
+        rw_map.by_counter().unwrap_err(),
+        RwBuildError::DuplicateRwCounter(2)
+    );
+}
+
+#[test]
+fn count_account_field_accesses_by_direction() {
+    let account = |rw_counter, is_write, field_tag| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        account_address: Address::zero(),
+        field_tag,
+        value: Word::zero(),
+        value_prev: Word::zero(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        account(1, false, AccountFieldTag::Balance),
+        account(2, false, AccountFieldTag::Balance),
+        account(3, true, AccountFieldTag::Balance),
+        account(4, false, AccountFieldTag::Nonce),
+        Rw::CallContext {
+            rw_counter: RwCounter(5),
+            is_write: false,
+            call_id: 1,
+            field_tag: CallContextFieldTag::CalleeAddress,
+            value: Word::zero(),
+        },
+    ]);
+
+    assert_eq!(
+        rw_map.count_account_field_accesses(AccountFieldTag::Balance, Some(false)),
+        2
+    );
+    assert_eq!(
+        rw_map.count_account_field_accesses(AccountFieldTag::Balance, None),
+        3
+    );
+    assert_eq!(
+        rw_map.count_call_context_accesses(CallContextFieldTag::CalleeAddress, Some(false)),
+        1
+    );
+    assert_eq!(
+        rw_map.count_call_context_accesses(CallContextFieldTag::CalleeAddress, Some(true)),
+        0
+    );
+}
+
+#[test]
+fn assignments_nonempty_on_empty_map() {
+    let rows = RwMap::default().assignments_nonempty(0);
+    assert_eq!(rows.len(), 1);
+    assert!(matches!(
+        rows[0],
+        Rw::Start {
+            rw_counter: RwCounter(1)
+        }
+    ));
+
+    assert_eq!(RwMap::default().assignments_nonempty(4).len(), 4);
+}
+
+#[test]
+fn check_call_context_ranges_rejects_deep_call() {
+    let call_context = |rw_counter, field_tag, value: u64| Rw::CallContext {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        field_tag,
+        value: Word::from(value),
+    };
+    let valid = RwMap::from_rows(vec![
+        call_context(1, CallContextFieldTag::Depth, 1024),
+        call_context(2, CallContextFieldTag::IsStatic, 1),
+        call_context(3, CallContextFieldTag::GasLeft, 1 << 40),
+    ]);
+    assert!(valid.check_call_context_ranges().is_ok());
+
+    let too_deep = RwMap::from_rows(vec![call_context(1, CallContextFieldTag::Depth, 2000)]);
+    let errs = too_deep.check_call_context_ranges().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].field_tag, CallContextFieldTag::Depth);
+    assert_eq!(errs[0].max, Word::from(1024));
+
+    let stack_overflow = RwMap::from_rows(vec![call_context(
+        1,
+        CallContextFieldTag::StackPointer,
+        1024,
+    )]);
+    let errs = stack_overflow.check_call_context_ranges().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].max, Word::from(1023));
+}
+
+#[test]
+fn interned_binary_round_trip() {
+    let addresses = [0xaa, 0xbb, 0xcc].map(Address::repeat_byte);
+    let rw_map = RwMap::from_rows((1..=1000).map(|rw_counter| Rw::AccountStorage {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address: addresses[rw_counter % 3],
+        storage_key: Word::from(rw_counter / 3),
+        value: Word::from(rw_counter),
+        value_prev: Word::zero(),
+        tx_id: 1,
+        committed_value: Word::zero(),
+    }));
+
+    let bytes = rw_map.to_interned_binary();
+    assert!(bytes.len() < rw_map.to_binary(false).len());
+    let parsed = RwMap::from_interned_binary(&bytes).unwrap();
+    assert_eq!(parsed.to_hex_dump(), rw_map.to_hex_dump());
+
+    let mixed = RwMap::from(&mock_container());
+    let parsed = RwMap::from_interned_binary(&mixed.to_interned_binary()).unwrap();
+    assert_eq!(parsed.to_hex_dump(), mixed.to_hex_dump());
+
+    let err = RwMap::from_interned_binary(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.line, 1000);
+}
+
+#[test]
+fn check_first_access_cold_detects_warm_first_access() {
+    let precompile = Address::from_low_u64_be(1);
+    let callee = Address::repeat_byte(0xaa);
+    let access = |rw_counter, account_address, is_warm_prev| Rw::TxAccessListAccount {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        tx_id: 1,
+        account_address,
+        is_warm: true,
+        is_warm_prev,
+    };
+    let prewarmed = BTreeSet::from([precompile]);
+
+    let valid = RwMap::from_rows(vec![
+        access(1, precompile, true),
+        access(2, callee, false),
+        access(3, callee, true),
+    ]);
+    assert!(valid.check_first_access_cold(&prewarmed).is_ok());
+
+    let warm_first = RwMap::from_rows(vec![access(1, precompile, true), access(2, callee, true)]);
+    let errs = warm_first.check_first_access_cold(&prewarmed).unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].tx_id, 1);
+    assert_eq!(errs[0].account_address, callee);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(2));
+}
+
+#[test]
+fn builder_edits_and_rebuilds() {
+    let mut builder = RwMap::from(&mock_container()).into_builder();
+
+    if let Some(Rw::Memory { byte, .. }) = builder.get_mut(2) {
+        *byte = 0x21;
+    }
+    assert!(matches!(builder.remove(3), Some(Rw::AccountStorage { .. })));
+    let rw_counter = builder.push(storage_write(0, 2, 0));
+    assert_eq!(rw_counter, 5);
+
+    let rw_map = builder.build().unwrap();
+    let rows = rw_map.by_counter().unwrap();
+    assert_eq!(rows.len(), 5);
+    assert!(matches!(rows[&2], Rw::Memory { byte: 0x21, .. }));
+    assert!(matches!(rows[&3], Rw::Stack { .. }));
+    assert!(matches!(rows[&5], Rw::AccountStorage { .. }));
+}
+
+#[test]
+fn call_depth_segments_for_nested_call() {
+    let depth = |rw_counter, call_id, depth: u64| Rw::CallContext {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id,
+        field_tag: CallContextFieldTag::Depth,
+        value: Word::from(depth),
+    };
+    let stack = |rw_counter, call_id| Rw::Stack {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id,
+        stack_pointer: 1023,
+        value: Word::zero(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        depth(1, 1, 1),
+        stack(2, 1),
+        depth(3, 2, 2),
+        stack(4, 2),
+        storage_write(5, 1, 0),
+        stack(6, 2),
+        stack(7, 1),
+    ]);
+
+    assert_eq!(
+        rw_map.call_depth_segments(),
+        vec![(1, 1..=2), (2, 3..=6), (1, 7..=7)]
+    );
+}
+
+#[test]
+fn rw_row_matches_treats_unknown_as_mismatch() {
+    let row = RwMap::from(&mock_container()).table_assignments()[0];
+    let assigned = row.table_assignment::<Fr>();
+    let expected = assigned.unwrap();
+    assert!(assigned.matches(&expected));
+
+    let mut other = expected;
+    other.rw_counter = Fr::from(10u64);
+    assert!(!assigned.matches(&other));
+
+    let mut unknown = assigned;
+    unknown.field_tag = Value::unknown();
+    assert!(!unknown.matches(&expected));
+}
+
+#[test]
+fn prune_start_removes_stray_start_rows() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::Start,
+        [3, 8, 42]
+            .into_iter()
+            .map(|rw_counter| Rw::Start {
+                rw_counter: RwCounter(rw_counter),
+            })
+            .collect(),
+    );
+
+    rw_map.prune_start();
+    assert!(rw_map.0[&Target::Start].is_empty());
+    assert_eq!(rw_map.table_assignments().len(), 5);
+}
+
+#[test]
+fn check_memory_within_size_detects_out_of_bounds_write() {
+    let memory_size = Rw::CallContext {
+        rw_counter: RwCounter(1),
+        is_write: false,
+        call_id: 1,
+        field_tag: CallContextFieldTag::MemorySize,
+        value: Word::from(2),
+    };
+    let memory_write = |rw_counter, memory_address| Rw::Memory {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        memory_address,
+        byte: 0x1,
+    };
+
+    let valid = RwMap::from_rows(vec![memory_size, memory_write(2, 63)]);
+    assert!(valid.check_memory_within_size().is_ok());
+
+    let beyond = RwMap::from_rows(vec![memory_size, memory_write(2, 63), memory_write(3, 64)]);
+    let errs = beyond.check_memory_within_size().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].call_id, 1);
+    assert_eq!(errs[0].memory_size, 2);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(3));
+}
+
+#[test]
+fn subset_keeps_only_requested_targets() {
+    let rw_map = RwMap::from(&mock_container());
+
+    let memory = rw_map.subset(&[Target::Memory]);
+    assert_eq!(memory.0.keys().collect::<Vec<_>>(), vec![&Target::Memory]);
+    assert_eq!(memory.0[&Target::Memory].len(), 2);
+    assert!(memory
+        .table_assignments()
+        .iter()
+        .all(|row| matches!(row, Rw::Memory { .. } | Rw::Start { .. })));
+
+    let with_start = rw_map.subset(&[Target::Start, Target::Memory]);
+    assert_eq!(
+        with_start.0.len(),
+        rw_map.0.contains_key(&Target::Start) as usize + 1
+    );
+}
+
+#[test]
+fn mpt_circuit_rows_keeps_first_accesses_in_address_order() {
+    let high = Address::repeat_byte(0xff);
+    let low = Address::repeat_byte(0x01);
+    let nonce = |rw_counter, account_address, value| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address,
+        field_tag: AccountFieldTag::Nonce,
+        value: Word::from(value),
+        value_prev: Word::from(value - 1),
+    };
+    let storage = |rw_counter, account_address, value| Rw::AccountStorage {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address,
+        storage_key: Word::one(),
+        value: Word::from(value),
+        value_prev: Word::from(value - 1),
+        tx_id: 1,
+        committed_value: Word::zero(),
+    };
+    let stack = Rw::Stack {
+        rw_counter: RwCounter(7),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::one(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        nonce(1, high, 1),
+        storage(2, high, 1),
+        nonce(3, low, 1),
+        nonce(4, high, 2),
+        storage(5, low, 1),
+        storage(6, high, 2),
+        stack,
+    ]);
+
+    let rows = rw_map.mpt_circuit_rows();
+    assert_eq!(
+        rows.iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![3, 5, 1, 2]
+    );
+}
+
+#[test]
+fn check_codehash_stability_detects_mid_execution_change() {
+    let empty_code_hash = CodeDB::empty_code_hash().to_word();
+    let code_hash_write = |rw_counter, value, value_prev| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address: Address::zero(),
+        field_tag: AccountFieldTag::CodeHash,
+        value,
+        value_prev,
+    };
+    let deployed = Word::from(0xc0de);
+
+    let create = RwMap::from_rows(vec![
+        code_hash_write(1, deployed, empty_code_hash),
+        code_hash_write(2, Word::zero(), deployed),
+    ]);
+    assert!(create.check_codehash_stability().is_ok());
+
+    let illegal = RwMap::from_rows(vec![
+        code_hash_write(1, deployed, empty_code_hash),
+        code_hash_write(2, Word::from(0xbad), deployed),
+    ]);
+    let errs = illegal.check_codehash_stability().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].account_address, Address::zero());
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(2));
+}
+
+#[test]
+fn sorted_groups_partition_sorted_rows() {
+    let rw_map = RwMap::from(&stale_reads_container());
+    let rows = rw_map.table_assignments();
+    let sorted = rw_map.sorted();
+    assert_eq!(sorted.rows(), rows);
+
+    let groups: Vec<(RwKey, &[Rw])> = sorted.sorted_groups().collect();
+    assert!(groups
+        .iter()
+        .all(|(key, group)| !group.is_empty() && group.iter().all(|row| row.key() == *key)));
+    assert!(groups.iter().tuple_windows().all(|(a, b)| a.0 < b.0));
+    let concatenated: Vec<Rw> = groups
+        .iter()
+        .flat_map(|(_, group)| group.iter().copied())
+        .collect();
+    assert_eq!(concatenated, rows);
+}
+
+#[test]
+fn rlc_split_with_equal_challenges_matches_rlc() {
+    let row = storage_write(3, 2, 1).table_assignment::<Fr>().unwrap();
+    let challenge = Fr::from(0x1234u64);
+    assert_eq!(row.rlc_split(challenge, challenge), row.rlc(challenge));
+    assert_ne!(
+        row.rlc_split(challenge, Fr::from(0x5678u64)),
+        row.rlc(challenge)
+    );
+}
+
+#[test]
+fn account_lifecycle_tracks_create_then_destroy() {
+    let code_hash = Word::from(0xc0de);
+    let code_hash_write = |rw_counter, account_address, value, value_prev| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address,
+        field_tag: AccountFieldTag::CodeHash,
+        value,
+        value_prev,
+    };
+    let addr = Address::repeat_byte(0xaa);
+    let rw_map = RwMap::from_rows(vec![
+        code_hash_write(1, addr, code_hash, Word::zero()),
+        code_hash_write(2, Address::zero(), code_hash, Word::zero()),
+        code_hash_write(3, addr, Word::zero(), code_hash),
+    ]);
+
+    assert_eq!(
+        rw_map.account_lifecycle(addr),
+        vec![(1, LifecycleEvent::Created), (3, LifecycleEvent::Destroyed)]
+    );
+}
+
+#[test]
+fn split_off_tx_moves_tx_and_call_rows() {
+    let tx_rows = |tx_id: usize| {
+        let call_id = tx_id * 10;
+        let rw_counter = tx_id * 3;
+        vec![
+            Rw::CallContext {
+                rw_counter: RwCounter(rw_counter - 2),
+                is_write: false,
+                call_id,
+                field_tag: CallContextFieldTag::TxId,
+                value: Word::from(tx_id),
+            },
+            Rw::Stack {
+                rw_counter: RwCounter(rw_counter - 1),
+                is_write: true,
+                call_id,
+                stack_pointer: 1023,
+                value: Word::one(),
+            },
+            Rw::AccountStorage {
+                rw_counter: RwCounter(rw_counter),
+                is_write: true,
+                account_address: Address::zero(),
+                storage_key: Word::one(),
+                value: Word::from(tx_id),
+                value_prev: Word::from(tx_id - 1),
+                tx_id,
+                committed_value: Word::zero(),
+            },
+        ]
+    };
+    let account = Rw::Account {
+        rw_counter: RwCounter(10),
+        is_write: true,
+        account_address: Address::zero(),
+        field_tag: AccountFieldTag::Nonce,
+        value: Word::one(),
+        value_prev: Word::zero(),
+    };
+    let mut rw_map = RwMap::from_rows((1..=3).flat_map(tx_rows).chain(std::iter::once(account)));
+
+    let tx_2 = rw_map.split_off_tx(2);
+    assert_eq!(
+        tx_2.table_assignments()
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .sorted()
+            .collect::<Vec<_>>(),
+        vec![4, 5, 6]
+    );
+    assert_eq!(
+        rw_map
+            .table_assignments()
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .sorted()
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3, 7, 8, 9, 10]
+    );
+}
+
+#[test]
+fn to_svg_heatmap_emits_one_cell_per_bucket_and_target() {
+    let rw_map = RwMap::from(&mock_container());
+
+    let svg = rw_map.to_svg_heatmap(4, 100);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    let targets = Target::iter()
+        .filter(|target| *target != Target::Start)
+        .count();
+    assert_eq!(svg.matches("<rect ").count(), 4 * targets);
+    assert_eq!(
+        svg.matches("<rect ").count(),
+        svg.matches("</rect>").count()
+    );
+    assert!(svg.contains("fill-opacity=\"1.000\""));
+}
+
+#[test]
+fn to_svg_heatmap_puts_counter_zero_in_first_column() {
+    let rw_map = RwMap::from_rows(vec![storage_write(0, 1, 0)]);
+
+    let svg = rw_map.to_svg_heatmap(2, 100);
+    assert!(svg.contains("<rect x=\"0\""));
+    assert!(svg.contains("<title>Storage: 1</title>"));
+}
+
+#[test]
+fn check_stack_pointer_monotone_per_call_detects_unwritten_read() {
+    let stack = |rw_counter, is_write, stack_pointer| Rw::Stack {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        call_id: 1,
+        stack_pointer,
+        value: Word::one(),
+    };
+
+    let valid = RwMap::from_rows(vec![stack(1, true, 1023), stack(2, false, 1023)]);
+    assert!(valid.check_stack_pointer_monotone_per_call().is_ok());
+
+    let invalid = RwMap::from_rows(vec![
+        stack(1, true, 1023),
+        stack(2, false, 1022),
+        stack(3, true, 1024),
+    ]);
+    let errs = invalid.check_stack_pointer_monotone_per_call().unwrap_err();
+    assert_eq!(errs.len(), 2);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(2));
+    assert_eq!(errs[0].reason, "read at a stack pointer never written");
+    assert_eq!(errs[1].row.rw_counter(), RwCounter(3));
+    assert_eq!(errs[1].reason, "stack pointer out of range");
+}
+
+#[test]
+fn memory_words_view_reconstructs_full_word() {
+    let memory_write = |rw_counter, call_id, memory_address, byte| Rw::Memory {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id,
+        memory_address,
+        byte,
+    };
+    let rows: Vec<Rw> = (0..32u8)
+        .map(|idx| memory_write(idx as usize + 1, 1, 0x20 + idx as u64, idx + 1))
+        .chain(std::iter::once(memory_write(33, 2, 0x20, 0xff)))
+        .collect();
+    let rw_map = RwMap::from_rows(rows);
+
+    let words = rw_map.memory_words_view(1);
+    let expected: Vec<u8> = (1..=32).collect();
+    assert_eq!(words.len(), 1);
+    assert_eq!(words[&0x20], (Word::from_big_endian(&expected), 32));
+}
+
+#[test]
+fn get_op_returns_none_for_out_of_range_ref() {
+    let rw_map = RwMap::from(&mock_container());
+    let len = rw_map.0[&Target::Stack].len();
+
+    assert!(rw_map
+        .get_op(OperationRef(Target::Stack, len - 1))
+        .is_some());
+    assert!(rw_map.get_op(OperationRef(Target::Stack, len)).is_none());
+    assert!(rw_map.get_op(OperationRef(Target::TxLog, 0)).is_none());
+}
+
+#[test]
+fn final_tx_refund_returns_latest_refund() {
+    let refund = |rw_counter, tx_id, value, value_prev| Rw::TxRefund {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        tx_id,
+        value,
+        value_prev,
+    };
+    let rw_map = RwMap::from_rows(vec![
+        refund(3, 1, 4800, 2400),
+        refund(1, 1, 2400, 0),
+        refund(5, 2, 100, 0),
+    ]);
+
+    assert_eq!(rw_map.final_tx_refund(1), Some(4800));
+    assert_eq!(rw_map.final_tx_refund(2), Some(100));
+    assert_eq!(rw_map.final_tx_refund(3), None);
+}
+
+#[test]
+fn check_receipt_status_detects_non_boolean_status() {
+    let receipt = |rw_counter, tx_id, field_tag, value| Rw::TxReceipt {
+        rw_counter: RwCounter(rw_counter),
+        is_write: false,
+        tx_id,
+        field_tag,
+        value,
+    };
+    let rw_map = RwMap::from_rows(vec![
+        receipt(1, 1, TxReceiptFieldTag::PostStateOrStatus, 1),
+        receipt(2, 1, TxReceiptFieldTag::CumulativeGasUsed, 21000),
+        receipt(3, 2, TxReceiptFieldTag::PostStateOrStatus, 2),
+    ]);
+
+    let errs = rw_map.check_receipt_status().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].tx_id, 2);
+    assert_eq!(errs[0].value, 2);
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(3));
+}
+
+#[test]
+fn into_target_streams_yields_every_row() {
+    let rw_map = RwMap::from(&stale_reads_container());
+    let len = rw_map.0.values().map(Vec::len).sum::<usize>();
+
+    let streams = rw_map.into_target_streams();
+    assert!(streams.iter().tuple_windows().all(|(a, b)| a.0 < b.0));
+    let drained: usize = streams
+        .into_iter()
+        .map(|(target, stream)| stream.inspect(|row| assert_eq!(row.tag(), target)).count())
+        .sum();
+    assert_eq!(drained, len);
+}
+
+#[test]
+fn canonicalize_equivalent_maps_identically() {
+    let rows = RwMap::from(&mock_container()).real_rows();
+    let built = RwMap::from_rows(rows.clone());
+    let reversed_with_start = RwMap::from_rows(rows.iter().rev().copied().chain([
+        Rw::Start {
+            rw_counter: RwCounter(1),
+        },
+        Rw::Start {
+            rw_counter: RwCounter(2),
+        },
+    ]));
+    let with_gaps = RwMap::from_rows(
+        rows.iter()
+            .map(|row| row.with_rw_counter(RwCounter(row.rw_counter().0 * 10))),
+    );
+
+    let canonical = built.canonicalize();
+    assert_eq!(reversed_with_start.canonicalize(), canonical);
+    assert_eq!(with_gaps.canonicalize(), canonical);
+    assert_eq!(canonical, rows);
+}
+
+#[test]
+fn tx_access_sets_split_reads_and_writes() {
+    let storage = |rw_counter, is_write, storage_key: u64, tx_id| Rw::AccountStorage {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        account_address: Address::zero(),
+        storage_key: Word::from(storage_key),
+        value: Word::one(),
+        value_prev: Word::one(),
+        tx_id,
+        committed_value: Word::one(),
+    };
+    let account = |rw_counter, is_write, field_tag| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        account_address: Address::zero(),
+        field_tag,
+        value: Word::one(),
+        value_prev: Word::one(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        Rw::CallContext {
+            rw_counter: RwCounter(1),
+            is_write: false,
+            call_id: 1,
+            field_tag: CallContextFieldTag::TxId,
+            value: Word::one(),
+        },
+        storage(2, false, 1, 1),
+        account(3, true, AccountFieldTag::Balance),
+        storage(4, true, 2, 1),
+        storage(5, true, 1, 2),
+        account(6, false, AccountFieldTag::Nonce),
+    ]);
+
+    let sets = rw_map.tx_access_sets(1);
+    assert_eq!(
+        sets.storage_reads,
+        BTreeSet::from([(Address::zero(), Word::from(1))])
+    );
+    assert_eq!(
+        sets.storage_writes,
+        BTreeSet::from([(Address::zero(), Word::from(2))])
+    );
+    assert!(sets.account_reads.is_empty());
+    assert_eq!(
+        sets.account_writes,
+        BTreeSet::from([(Address::zero(), AccountFieldTag::Balance)])
+    );
+    assert_eq!(rw_map.tx_access_sets(3), AccessSets::default());
+}
+
+#[test]
+fn lookup_estimate_counts_first_access_groups() {
+    let rw_map = RwMap::from_rows(vec![
+        storage_write(1, 1, 0),
+        storage_write(2, 2, 1),
+        Rw::AccountStorage {
+            rw_counter: RwCounter(3),
+            is_write: false,
+            account_address: Address::zero(),
+            storage_key: Word::from(2),
+            value: Word::zero(),
+            value_prev: Word::zero(),
+            tx_id: 1,
+            committed_value: Word::zero(),
+        },
+        Rw::Account {
+            rw_counter: RwCounter(4),
+            is_write: false,
+            account_address: Address::zero(),
+            field_tag: AccountFieldTag::Nonce,
+            value: Word::zero(),
+            value_prev: Word::zero(),
+        },
+        Rw::Memory {
+            rw_counter: RwCounter(5),
+            is_write: true,
+            call_id: 1,
+            memory_address: 0,
+            byte: 1,
+        },
+    ]);
+
+    assert_eq!(
+        rw_map.lookup_estimate(),
+        LookupEstimate {
+            mpt: 3,
+            byte_range: 1,
+            call_context: 0,
+        }
+    );
+}
+
+#[test]
+fn assert_matches_container_accepts_fresh_conversion() {
+    let container = mock_container();
+    let mut rw_map = RwMap::from(&container);
+    rw_map.assert_matches_container(&container);
+
+    rw_map.offset_rw_counters(100);
+    rw_map.assert_matches_container(&container);
+}
+
+#[test]
+#[should_panic(expected = "RwMap doesn't match the container")]
+fn assert_matches_container_rejects_mutated_map() {
+    let container = mock_container();
+    let mut rw_map = RwMap::from(&container);
+    rw_map.0.get_mut(&Target::Stack).unwrap().pop();
+    rw_map.assert_matches_container(&container);
+}
+
+#[test]
+fn hot_keys_ranks_most_accessed_slot_first() {
+    let storage = |rw_counter, storage_key: u64| Rw::AccountStorage {
+        rw_counter: RwCounter(rw_counter),
+        is_write: false,
+        account_address: Address::zero(),
+        storage_key: Word::from(storage_key),
+        value: Word::zero(),
+        value_prev: Word::zero(),
+        tx_id: 1,
+        committed_value: Word::zero(),
+    };
+    let rows: Vec<Rw> = (1..=5)
+        .map(|rw_counter| storage(rw_counter, 7))
+        .chain([storage(6, 3), storage(7, 1)])
+        .collect();
+    let rw_map = RwMap::from_rows(rows);
+
+    let hot = rw_map.hot_keys(2);
+    assert_eq!(hot.len(), 2);
+    assert_eq!((hot[0].0.storage_key, hot[0].1), (Word::from(7), 5));
+    assert_eq!((hot[1].0.storage_key, hot[1].1), (Word::from(1), 1));
+}
+
+#[test]
+fn check_value_encoding_accepts_full_words() {
+    let rw_map = RwMap::from_rows(vec![Rw::AccountStorage {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        account_address: Address::zero(),
+        storage_key: U256::MAX,
+        value: U256::MAX,
+        value_prev: U256::MAX - 1,
+        tx_id: 1,
+        committed_value: U256::MAX,
+    }]);
+
+    // The limbs are 128 bits, which always fit the 254 bits of Fr.
+    assert!(rw_map.check_value_encoding::<Fr>().is_ok());
+}
+
+#[test]
+fn check_value_encoding_reports_oversized_limb() {
+    // A 61-bit test modulus, as no limb reaches the modulus of Fr.
+    let modulus = Word::from((1u64 << 61) - 1);
+    let row = Rw::AccountStorage {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        account_address: Address::zero(),
+        storage_key: Word::from(1),
+        value: Word::from(u64::MAX),
+        value_prev: Word::one() << 200,
+        tx_id: 1,
+        committed_value: Word::zero(),
+    };
+    let errs = encoding_errors(&row, modulus);
+
+    assert_eq!(
+        errs.iter().map(|err| err.column).collect::<Vec<_>>(),
+        vec!["value", "value_prev"]
+    );
+    assert!(errs.iter().all(|err| err.row == row));
+    assert!(encoding_errors(&storage_write(1, 2, 1), modulus).is_empty());
+}
+
+#[test]
+fn rows_for_account_excludes_colliding_memory_rows() {
+    let addr = Address::from_low_u64_be(0x40);
+    let memory = Rw::Memory {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        call_id: 1,
+        memory_address: 0x40,
+        byte: 1,
+    };
+    let account = Rw::Account {
+        rw_counter: RwCounter(2),
+        is_write: false,
+        account_address: addr,
+        field_tag: AccountFieldTag::Balance,
+        value: Word::zero(),
+        value_prev: Word::zero(),
+    };
+    let access_list = Rw::TxAccessListAccount {
+        rw_counter: RwCounter(3),
+        is_write: true,
+        tx_id: 1,
+        account_address: addr,
+        is_warm: true,
+        is_warm_prev: false,
+    };
+    let rw_map = RwMap::from_rows(vec![memory, account, access_list]);
+    assert_eq!(memory.address(), Some(addr));
+
+    let rows = rw_map.rows_for_account(addr);
+    assert_eq!(
+        rows.iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+}
+
+#[test]
+fn index_by_step_groups_rows_in_step_windows() {
+    let rw_map = RwMap::from(&stale_reads_container());
+
+    let steps = rw_map.index_by_step(&[2, 4, 6]);
+    let counters: BTreeMap<usize, Vec<usize>> = steps
+        .iter()
+        .map(|(step, rows)| (*step, rows.iter().map(|row| row.rw_counter().0).collect()))
+        .collect();
+    assert_eq!(
+        counters,
+        BTreeMap::from([(0, vec![2, 3]), (1, vec![4, 5]), (2, vec![6, 7])])
+    );
+}
+
+#[test]
+fn dedup_call_context_reads_collapses_identical_reads() {
+    let call_context = |rw_counter, is_write, field_tag| Rw::CallContext {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        call_id: 1,
+        field_tag,
+        value: Word::one(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        call_context(1, true, CallContextFieldTag::TxId),
+        call_context(2, false, CallContextFieldTag::TxId),
+        call_context(3, false, CallContextFieldTag::TxId),
+        call_context(4, false, CallContextFieldTag::GasLeft),
+        call_context(5, false, CallContextFieldTag::TxId),
+        call_context(6, false, CallContextFieldTag::GasLeft),
+        call_context(7, false, CallContextFieldTag::TxId),
+    ]);
+
+    let deduped = rw_map.dedup_call_context_reads();
+    assert_eq!(
+        deduped.0[&Target::CallContext]
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .sorted()
+            .collect::<Vec<_>>(),
+        vec![1, 2, 4, 6]
+    );
+
+    // A row sharing the counter of a dropped read is kept.
+    let gas_left = call_context(3, false, CallContextFieldTag::GasLeft);
+    let mut rows = rw_map.0[&Target::CallContext].clone();
+    rows.push(gas_left);
+    let deduped = RwMap::from_rows(rows).dedup_call_context_reads();
+    let deduped = &deduped.0[&Target::CallContext];
+    assert_eq!(deduped.len(), 5);
+    assert!(deduped.contains(&gas_left));
+}
+
+#[test]
+fn new_map_has_one_start_row() {
+    let rows = RwMap::new().table_assignments();
+    assert_eq!(rows.len(), 1);
+    assert!(matches!(
+        rows[0],
+        Rw::Start {
+            rw_counter: RwCounter(1)
+        }
+    ));
+    assert!(RwMap::default().table_assignments().is_empty());
+}
+
+#[test]
+fn check_call_return_wiring_detects_wrong_success_flag() {
+    let call_context = |rw_counter, call_id, field_tag, value: u64| Rw::CallContext {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id,
+        field_tag,
+        value: Word::from(value),
+    };
+    let rw_map_with_result = |result: u64| {
+        RwMap::from_rows(vec![
+            call_context(1, 1, CallContextFieldTag::CallerId, 0),
+            Rw::Stack {
+                rw_counter: RwCounter(2),
+                is_write: true,
+                call_id: 1,
+                stack_pointer: 1017,
+                value: Word::from(result),
+            },
+            call_context(3, 4, CallContextFieldTag::CallerId, 1),
+            call_context(4, 4, CallContextFieldTag::IsSuccess, 1),
+        ])
+    };
+
+    assert!(rw_map_with_result(1).check_call_return_wiring().is_ok());
+
+    let errs = rw_map_with_result(0)
+        .check_call_return_wiring()
+        .unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!((errs[0].call_id, errs[0].caller_id), (4, 1));
+    assert_eq!(errs[0].expected, Word::one());
+    assert_eq!(errs[0].stack_row.map(|row| row.rw_counter().0), Some(2));
+}
+
+#[test]
+fn column_iter_yields_padded_rw_counters() {
+    let rw_map = RwMap::from(&mock_container());
+
+    let rw_counters: Vec<Fr> = rw_map.column_iter(0, 8).collect();
+    // 3 Start rows, then memory (2, 5), stack (1, 4) and storage (3) in
+    // sort-key order
+    let expected: Vec<Fr> = [1u64, 2, 3, 2, 5, 1, 4, 3]
+        .into_iter()
+        .map(Fr::from)
+        .collect();
+    assert_eq!(rw_counters, expected);
+}
+
+#[test]
+fn merge_for_mpt_moves_first_access_to_earlier_chunk() {
+    let late_chunk = RwMap::from_rows(vec![storage_write(5, 2, 1)]);
+    let early_chunk = RwMap::from_rows(vec![storage_write(2, 1, 0)]);
+    assert_eq!(late_chunk.mpt_circuit_rows()[0].rw_counter(), RwCounter(5));
+
+    let (merged, first_accesses) = late_chunk.merge_for_mpt(early_chunk);
+    assert_eq!(merged.0[&Target::Storage].len(), 2);
+    assert_eq!(first_accesses.len(), 1);
+    assert_eq!(first_accesses[0].rw_counter(), RwCounter(2));
+}
+
+#[test]
+fn memory_value_word_widens_byte() {
+    let memory = Rw::Memory {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        call_id: 1,
+        memory_address: 0x20,
+        byte: 0xab,
+    };
+    assert_eq!(memory.memory_value_word(), Some(Word::from(0xab)));
+    assert_eq!(storage_write(2, 1, 0).memory_value_word(), None);
+}
+
+#[test]
+fn check_refund_cap_detects_excess_refund() {
+    let rw_map = RwMap::from_rows(vec![Rw::TxRefund {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        tx_id: 1,
+        value: 4800,
+        value_prev: 0,
+    }]);
+
+    assert!(rw_map.check_refund_cap(1, 24000).is_ok());
+    assert_eq!(
+        rw_map.check_refund_cap(1, 21000),
+        Err(RefundCapError {
+            tx_id: 1,
+            refund: 4800,
+            cap: 4200,
+        })
+    );
+    assert!(rw_map.check_refund_cap(2, 0).is_ok());
+}
+
+#[test]
+fn narrate_key_describes_read_write_read() {
+    let stack = |rw_counter, is_write, value: u64| Rw::Stack {
+        rw_counter: RwCounter(rw_counter),
+        is_write,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::from(value),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        stack(80, false, 9),
+        stack(40, false, 0),
+        stack(55, true, 9),
+    ]);
+
+    assert_eq!(
+        rw_map.narrate_key(stack(1, true, 0).key()),
+        "counter 40: READ init=0 got 0; counter 55: WRITE 0→9; counter 80: READ got 9."
+    );
+}
+
+#[test]
+fn assert_counters_from_container_accepts_conversion() {
+    let container = stale_reads_container();
+    assert_counters_from_container(&RwMap::from(&container), &container);
+}
+
+#[test]
+fn assignments_pow2_pads_to_next_power_of_two() {
+    let rw_map = RwMap::from(&mock_container());
+    assert_eq!(rw_map.real_rows().len(), 5);
+
+    let (rows, k) = rw_map.assignments_pow2(3);
+    assert_eq!(k, 4);
+    assert_eq!(rows.len(), 16);
+    assert_eq!(
+        rows.iter().filter(|row| row.is_padding_or_start()).count(),
+        11
+    );
+}
+
+#[test]
+fn try_get_returns_none_for_missing_rows() {
+    assert!(RwMap::default().try_get(Target::Stack, 0).is_none());
+
+    let rw_map = RwMap::from(&mock_container());
+    assert!(rw_map.try_get(Target::TxLog, 0).is_none());
+    let len = rw_map.0[&Target::Stack].len();
+    assert_eq!(
+        rw_map.try_get(Target::Stack, 0).map(|row| row.rw_counter()),
+        Some(rw_map[(Target::Stack, 0)].rw_counter())
+    );
+    assert!(rw_map.try_get(Target::Stack, len).is_none());
+}
+
+#[test]
+fn iter_by_tag_sorts_out_of_order_rows() {
+    let memory = |rw_counter| Rw::Memory {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        memory_address: rw_counter as u64,
+        byte: 0,
+    };
+    let rw_map = RwMap::from_rows(vec![memory(5), memory(2), memory(9), memory(1)]);
+
+    assert_eq!(
+        rw_map
+            .iter_by_tag(Target::Memory)
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 5, 9]
+    );
+    assert_eq!(rw_map.iter_by_tag(Target::Stack).count(), 0);
+}
+
+#[cfg(feature = "rw-serde")]
+#[test]
+fn serde_round_trips_every_variant() {
+    let address = Address::repeat_byte(0xab);
+    let rw_map = RwMap::from_rows(vec![
+        Rw::Start {
+            rw_counter: RwCounter(1),
+        },
+        Rw::TxAccessListAccount {
+            rw_counter: RwCounter(1),
+            is_write: true,
+            tx_id: 1,
+            account_address: address,
+            is_warm: true,
+            is_warm_prev: false,
+        },
+        Rw::TxAccessListAccountStorage {
+            rw_counter: RwCounter(2),
+            is_write: true,
+            tx_id: 1,
+            account_address: address,
+            storage_key: Word::MAX,
+            is_warm: true,
+            is_warm_prev: false,
+        },
+        Rw::TxRefund {
+            rw_counter: RwCounter(3),
+            is_write: true,
+            tx_id: 1,
+            value: 4800,
+            value_prev: 0,
+        },
+        Rw::Account {
+            rw_counter: RwCounter(4),
+            is_write: true,
+            account_address: address,
+            field_tag: AccountFieldTag::Balance,
+            value: Word::from(100),
+            value_prev: Word::from(200),
+        },
+        Rw::AccountStorage {
+            rw_counter: RwCounter(5),
+            is_write: true,
+            account_address: address,
+            storage_key: Word::one(),
+            value: Word::from(2),
+            value_prev: Word::one(),
+            tx_id: 1,
+            committed_value: Word::one(),
+        },
+        Rw::CallContext {
+            rw_counter: RwCounter(6),
+            is_write: false,
+            call_id: 1,
+            field_tag: CallContextFieldTag::TxId,
+            value: Word::one(),
+        },
+        Rw::Stack {
+            rw_counter: RwCounter(7),
+            is_write: true,
+            call_id: 1,
+            stack_pointer: 1023,
+            value: Word::from(0xdead),
+        },
+        Rw::Memory {
+            rw_counter: RwCounter(8),
+            is_write: true,
+            call_id: 1,
+            memory_address: 0x40,
+            byte: 0xff,
+        },
+        Rw::TxLog {
+            rw_counter: RwCounter(9),
+            is_write: true,
+            tx_id: 1,
+            log_id: 1,
+            field_tag: TxLogFieldTag::Topic,
+            index: 0,
+            value: Word::from(0xbeef),
+        },
+        Rw::TxReceipt {
+            rw_counter: RwCounter(10),
+            is_write: false,
+            tx_id: 1,
+            field_tag: TxReceiptFieldTag::PostStateOrStatus,
+            value: 1,
+        },
+    ]);
+
+    let json = serde_json::to_string(&rw_map).unwrap();
+    assert!(json.contains("\"0xabababababababababababababababababababab\""));
+    let parsed: RwMap = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.0.len(), Target::iter().count());
+    assert_eq!(parsed.table_assignments(), rw_map.table_assignments());
+}
+
+#[test]
+fn merge_offsets_counters_and_drops_start_rows() {
+    let stack = |rw_counter| Rw::Stack {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::from(rw_counter),
+    };
+    let memory = |rw_counter| Rw::Memory {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        memory_address: rw_counter as u64,
+        byte: rw_counter as u8,
+    };
+    let mut rw_map = RwMap::from_rows(vec![
+        Rw::Start {
+            rw_counter: RwCounter(1),
+        },
+        stack(1),
+        stack(2),
+    ]);
+    let other = RwMap::from_rows(vec![
+        Rw::Start {
+            rw_counter: RwCounter(1),
+        },
+        memory(1),
+        memory(2),
+    ]);
+
+    rw_map.merge(other, 2);
+    rw_map.check_rw_counter_sanity();
+    assert_eq!(rw_map.0[&Target::Start].len(), 1);
+    assert_eq!(
+        rw_map.0[&Target::Memory]
+            .iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn max_rw_counter_ignores_start_rows() {
+    assert_eq!(RwMap::default().max_rw_counter(), 0);
+    assert_eq!(RwMap::new().max_rw_counter(), 0);
+
+    let rw_map = RwMap::from_rows(vec![
+        Rw::Start {
+            rw_counter: RwCounter(9),
+        },
+        storage_write(3, 1, 0),
+        storage_write(7, 2, 1),
+    ]);
+    assert_eq!(rw_map.max_rw_counter(), 7);
+}
+
+#[test]
+fn memory_word_assembles_latest_bytes() {
+    let memory = |rw_counter, memory_address: u64, byte| Rw::Memory {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        call_id: 1,
+        memory_address,
+        byte,
+    };
+    let mut rows: Vec<Rw> = (0..32u64)
+        .map(|i| memory(i as usize + 1, 0x10 + i, 0))
+        .collect();
+    rows.push(memory(33, 0x2f, 0xff));
+    let rw_map = RwMap::from_rows(rows);
+
+    assert_eq!(rw_map.memory_word(1, 0x10), Some(Word::from(0xff)));
+    assert_eq!(rw_map.memory_word(1, 0x11), None);
+    assert_eq!(rw_map.memory_word(2, 0x10), None);
+    assert_eq!(rw_map.memory_word(1, u64::MAX - 16), None);
+}
+
+#[test]
+fn verify_value_reports_corrupted_read() {
+    let mut rw_map = RwMap::from(&mock_container());
+    assert!(rw_map.verify_value().is_ok());
+
+    let memory_read = rw_map
+        .0
+        .get_mut(&Target::Memory)
+        .unwrap()
+        .last_mut()
+        .unwrap();
+    if let Rw::Memory { byte, .. } = memory_read {
+        *byte ^= 0xff;
+    }
+    let errs = rw_map.verify_value().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].reason, "non-first access reads don't change value");
+    assert_eq!(errs[0].row.rw_counter(), RwCounter(5));
+    assert_eq!(
+        rw_map.table_assignments()[errs[0].idx].rw_counter(),
+        RwCounter(5)
+    );
+}
+
+#[test]
+fn from_rows_inverts_table_assignments() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(
+        Target::Start,
+        vec![Rw::Start {
+            rw_counter: RwCounter(1),
+        }],
+    );
+    let rows = rw_map.table_assignments();
+    let rebuilt = RwMap::from_rows(rows.clone());
+    assert_eq!(
+        rebuilt.0[&Target::Start].len(),
+        rw_map.0[&Target::Start].len()
+    );
+    assert_eq!(rebuilt.table_assignments(), rows);
+}
+
+#[test]
+fn tag_counts_orders_by_target() {
+    let mut rw_map = RwMap::from(&mock_container());
+    rw_map.0.insert(Target::TxLog, vec![]);
+    assert_eq!(
+        rw_map.tag_counts().into_iter().collect::<Vec<_>>(),
+        vec![
+            (Target::Memory, 2),
+            (Target::Stack, 2),
+            (Target::Storage, 1)
+        ]
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_table_assignments_matches_serial_sort() {
+    let rw_map = RwMap::from(&stale_reads_container());
+    let mut serial: Vec<Rw> = rw_map.0.values().flatten().cloned().collect();
+    serial.sort_by_key(sort_key);
+    assert_eq!(rw_map.table_assignments(), serial);
+}
+
+#[test]
+fn set_rw_counter_on_each_variant() {
+    for (idx, mut row) in one_row_of_each_variant().into_iter().enumerate() {
+        row.set_rw_counter(RwCounter(100 + idx));
+        assert_eq!(row.rw_counter(), RwCounter(100 + idx));
+    }
+}
+
+#[test]
+fn is_read_complements_is_write() {
+    for row in one_row_of_each_variant() {
+        assert_eq!(row.is_read(), !row.is_write(), "{:?}", row);
+    }
+    assert!(Rw::Start {
+        rw_counter: RwCounter(1)
+    }
+    .is_read());
+    assert!(!storage_write(1, 1, 0).is_read());
+}
+
+#[test]
+fn value_as_word_widens_small_values() {
+    let rows = one_row_of_each_variant();
+    for row in &rows {
+        assert_eq!(row.value_as_word(), row.value_assignment(), "{:?}", row);
+    }
+    let value_of = |tag| {
+        rows.iter()
+            .find(|row| row.tag() == tag)
+            .unwrap()
+            .value_as_word()
+    };
+    assert_eq!(value_of(Target::TxRefund), Word::from(15000));
+    assert_eq!(value_of(Target::TxReceipt), Word::one());
+    assert_eq!(value_of(Target::Memory), Word::from(0xab));
+}
+
+#[test]
+fn rows_in_rwc_range_spans_targets() {
+    let rw_map = RwMap::from(&mock_container());
+    let rows = rw_map.rows_in_rwc_range(2..5);
+    assert_eq!(
+        rows.iter()
+            .map(|row| row.rw_counter().0)
+            .collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+    assert_eq!(
+        rows.iter().map(|row| row.tag()).collect::<Vec<_>>(),
+        vec![Target::Memory, Target::Storage, Target::Stack]
+    );
+    assert!(rw_map.rows_in_rwc_range(6..10).is_empty());
+}
+
+#[test]
+fn rw_rows_compare_by_value() {
+    let memory = |byte| Rw::Memory {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        call_id: 1,
+        memory_address: 0x20,
+        byte,
+    };
+    assert_eq!(memory(1), memory(1));
+    assert_ne!(memory(1), memory(2));
+    assert_eq!(HashSet::from([memory(1), memory(1), memory(2)]).len(), 2);
+}
+
+#[test]
+fn renumber_start_rows_collapses_duplicates() {
+    let start = |rw_counter| Rw::Start {
+        rw_counter: RwCounter(rw_counter),
+    };
+    let mut rw_map = RwMap::from_rows([start(3), start(1), start(3), start(7)]);
+    rw_map.renumber_start_rows();
+    let expected = vec![start(1), start(2), start(3), start(4)];
+    assert_eq!(rw_map.0[&Target::Start], expected);
+    rw_map.renumber_start_rows();
+    assert_eq!(rw_map.0[&Target::Start], expected);
+}
+
+#[test]
+fn rw_row_from_values_round_trip() {
+    for rw in one_row_of_each_variant() {
+        let row = rw.table_assignment::<Fr>().unwrap();
+        assert_eq!(RwRow::from_values(row.values()).values(), row.values());
+    }
+}
+
+#[test]
+fn validate_invariants_checks_topic_index() {
+    let topic = |rw_counter, index| Rw::TxLog {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        tx_id: 1,
+        log_id: 1,
+        field_tag: TxLogFieldTag::Topic,
+        index,
+        value: Word::one(),
+    };
+    assert!(topic(1, 3).validate_invariants().is_ok());
+    assert_eq!(
+        topic(2, 4).validate_invariants(),
+        Err("log topic index 4 is not below 4".to_string())
+    );
+
+    let rw_map = RwMap::from_rows(one_row_of_each_variant().into_iter().chain([topic(12, 4)]));
+    let errs = rw_map.validate_all().unwrap_err();
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs[0].row, topic(12, 4));
+}
+
+#[test]
+fn storage_slots_touched_dedups_slots() {
+    let rw_map = RwMap::from_rows(vec![
+        storage_write(1, 1, 0),
+        storage_write(2, 2, 1),
+        storage_write(3, 3, 2),
+    ]);
+    assert_eq!(
+        rw_map.storage_slots_touched(),
+        BTreeSet::from([(Address::zero(), Word::from(1))])
+    );
+    assert!(RwMap::new().storage_slots_touched().is_empty());
+}
+
+#[test]
+fn accounts_touched_groups_fields_by_account() {
+    let account = |rw_counter, field_tag| Rw::Account {
+        rw_counter: RwCounter(rw_counter),
+        is_write: true,
+        account_address: Address::repeat_byte(0xaa),
+        field_tag,
+        value: Word::one(),
+        value_prev: Word::zero(),
+    };
+    let rw_map = RwMap::from_rows(vec![
+        account(1, AccountFieldTag::Balance),
+        account(2, AccountFieldTag::Nonce),
+        account(3, AccountFieldTag::Balance),
+        storage_write(4, 1, 0),
+    ]);
+    assert_eq!(
+        rw_map.accounts_touched(),
+        BTreeMap::from([(
+            Address::repeat_byte(0xaa),
+            BTreeSet::from([AccountFieldTag::Nonce, AccountFieldTag::Balance])
+        )])
+    );
+    assert!(RwMap::new().accounts_touched().is_empty());
+}
+
+#[test]
+fn rw_counter_converts_and_offsets() {
+    assert_eq!(RwCounter::from(4), RwCounter(4));
+    assert_eq!(usize::from(RwCounter(4)), 4);
+    assert_eq!(RwCounter::from(RWCounter(4)), RwCounter(4));
+    assert_eq!(RwCounter(4) + 2, RwCounter(6));
+    assert!(RwCounter(4) < RwCounter(6));
+    assert_eq!(RwCounter::default(), RwCounter(0));
+
+    let row = storage_write(4, 1, 0);
+    assert_eq!(
+        row.with_rw_counter(row.rw_counter() + 2).rw_counter(),
+        RwCounter(6)
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn rw_counter_usize_matches_rw_counter() {
+    for row in one_row_of_each_variant() {
+        assert_eq!(row.rw_counter_usize(), row.rw_counter().0);
+    }
+}
+
+fn tx_log_topic(log_id: u64) -> Rw {
+    Rw::TxLog {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        tx_id: 1,
+        log_id,
+        field_tag: TxLogFieldTag::Topic,
+        index: 2,
+        value: Word::one(),
+    }
+}
+
+#[test]
+fn hex_dump_round_trip_keeps_wide_log_id() {
+    let rw_map = RwMap::from_rows([tx_log_topic(70_000)]);
+    let parsed = RwMap::from_hex_dump(&rw_map.to_hex_dump()).unwrap();
+    assert_eq!(parsed.table_assignments(), rw_map.table_assignments());
+}
+
+#[test]
+fn interned_binary_round_trip_keeps_wide_log_id() {
+    let rw_map = RwMap::from_rows([tx_log_topic(70_000)]);
+    let parsed = RwMap::from_interned_binary(&rw_map.to_interned_binary()).unwrap();
+    assert_eq!(parsed.table_assignments(), rw_map.table_assignments());
+}
+
+#[test]
+fn hex_dump_rejects_oversized_fields() {
+    let stack = Rw::Stack {
+        rw_counter: RwCounter(1),
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: Word::one(),
+    };
+    let dump = RwMap::from_rows([stack]).to_hex_dump();
+    let fields: Vec<&str> = dump.trim().split(':').collect();
+    let oversized = format!("1{}", "0".repeat(32));
+    // rw_counter, id (call_id) and address (stack pointer)
+    for column in [1, 3, 4] {
+        let mut line = fields.clone();
+        line[column] = &oversized;
+        let err = RwMap::from_hex_dump(&line.join(":")).unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("exceeds usize"), "{}", err.message);
+    }
+}